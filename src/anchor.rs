@@ -0,0 +1,58 @@
+//! Expansion anchors, from which the tree is shown in full
+//!
+//! An anchor is either a tree type, like the historical `--expand-from`, or a
+//! specific container given by one of its marks, its id or its name.
+
+use std::str::FromStr;
+
+use crate::{Node, TreeType};
+
+#[derive(Clone, Debug)]
+pub enum Anchor {
+    /// Every node of this type
+    TreeType(TreeType),
+    /// The containers with this mark
+    Mark(String),
+    /// The container with this id
+    ConId(u64),
+    /// The nodes whose name matches this glob
+    Name(glob::Pattern),
+}
+
+impl Anchor {
+    pub fn matches(&self, node: &Node) -> bool {
+        match self {
+            Self::TreeType(tree_type) => node.tree_type == *tree_type,
+            Self::Mark(mark) => node.marks.contains(mark),
+            Self::ConId(id) => node.id == Some(*id),
+            Self::Name(pattern) => node.name.as_ref().is_some_and(|n| pattern.matches(n)),
+        }
+    }
+
+    /// Whether the anchor designates specific containers, which are then
+    /// shown even away from the focus
+    pub const fn is_specific(&self) -> bool {
+        !matches!(self, Self::TreeType(_))
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        match st.split_once(':') {
+            Some(("mark", mark)) => Ok(Self::Mark(mark.to_owned())),
+            Some(("id", id)) => id
+                .parse()
+                .map(Self::ConId)
+                .map_err(|e| format!("Invalid container id: {e}")),
+            Some(("name", name)) => glob::Pattern::new(name)
+                .map(Self::Name)
+                .map_err(|e| format!("Invalid name pattern: {e}")),
+            // Accept both i3's names and the ones of the command line
+            _ => TreeType::try_from(st)
+                .or_else(|e| <TreeType as clap::ValueEnum>::from_str(st, true).map_err(|_| e))
+                .map(Self::TreeType),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Icons and colors of the applications, by window class and instance
+//!
+//! The map is a TOML file listing applications, the first one matching a
+//! window giving its glyph and color:
+//!
+//! ```toml
+//! [[app]]
+//! class = "^firefox$"
+//! glyph = "🦊"
+//! color = "#ff7139"
+//! ```
+//!
+//! Windows of unmapped classes get a color derived from their class, which
+//! stays the same from one run to the next.
+
+use regex::{Regex, RegexBuilder};
+use serde_derive::Deserialize;
+
+use crate::Node;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawApp {
+    class: Option<String>,
+    instance: Option<String>,
+    glyph: Option<String>,
+    color: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMap {
+    #[serde(default)]
+    app: Vec<RawApp>,
+}
+
+#[derive(Clone, Debug)]
+struct App {
+    class: Option<Regex>,
+    instance: Option<Regex>,
+    glyph: Option<String>,
+    color: Option<String>,
+}
+
+impl App {
+    fn matches(&self, class: Option<&str>, instance: Option<&str>) -> bool {
+        let check = |re: &Option<Regex>, value: Option<&str>| {
+            re.as_ref()
+                .is_none_or(|re| value.is_some_and(|v| re.is_match(v)))
+        };
+        check(&self.class, class) && check(&self.instance, instance)
+    }
+}
+
+/// How an application is shown
+pub struct Appearance<'a> {
+    pub glyph: Option<&'a str>,
+    pub color: String,
+}
+
+/// Applications, in the order they are tried
+#[derive(Clone, Debug)]
+pub struct AppMap(Vec<App>);
+
+/// Reads an application map file
+pub fn load(path: &str) -> Result<AppMap, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read application map \"{path}\": {e}"))?;
+    let raw: RawMap =
+        toml::from_str(&source).map_err(|e| format!("Invalid application map \"{path}\": {e}"))?;
+    // Applications are not consistent about the case of their classes
+    let regex = |re: Option<String>| {
+        re.map(|re| {
+            RegexBuilder::new(&re)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Invalid regex \"{re}\" in application map: {e}"))
+        })
+        .transpose()
+    };
+    raw.app
+        .into_iter()
+        .map(|app| {
+            if app.class.is_none() && app.instance.is_none() {
+                return Err(String::from(
+                    "Every application needs a class or an instance",
+                ));
+            }
+            Ok(App {
+                class: regex(app.class)?,
+                instance: regex(app.instance)?,
+                glyph: app.glyph,
+                color: app.color,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(AppMap)
+}
+
+/// Color picked from the hash of the class, with the same lightness for all
+fn hashed_color(class: &str) -> String {
+    // FNV-1a, which unlike the hasher of the standard library is meant to
+    // stay the same across releases
+    let hash = class.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    // Reasoning: The hue only needs a thousand steps
+    #[allow(clippy::cast_precision_loss)]
+    let hue = (hash % 1000) as f64 / 1000_f64;
+    format!("{hue:.3} 0.350 0.950")
+}
+
+impl AppMap {
+    /// Glyph and color of a window or a placeholder
+    pub fn appearance(&self, node: &Node) -> Option<Appearance<'_>> {
+        if !node.is_window() && !node.is_placeholder() {
+            return None;
+        }
+        let (class, instance) = node.application();
+        let app = self
+            .0
+            .iter()
+            .find(|app| app.matches(class.as_deref(), instance.as_deref()));
+        let color = app.and_then(|app| app.color.clone()).or_else(|| {
+            class
+                .or(instance)
+                .map(|name| hashed_color(&name.to_lowercase()))
+        })?;
+        Some(Appearance {
+            glyph: app.and_then(|app| app.glyph.as_deref()),
+            color,
+        })
+    }
+}
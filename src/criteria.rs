@@ -0,0 +1,281 @@
+//! i3 criteria, as used in `for_window` rules and command prefixes
+//!
+//! Criteria look like `[class="^Firefox$" con_mark="web" floating]`. Every
+//! criterion of a set has to match for the set to match a container.
+//! `con_id=__focused__` matches the focused container, while the other
+//! criteria would need the values of the focused window, so they reject
+//! `__focused__` rather than take it as a regex.
+
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::{Node, WindowProperties};
+
+/// Value standing for the focused container
+const FOCUSED: &str = "__focused__";
+
+#[derive(Clone, Debug)]
+enum Criterion {
+    Class(Regex),
+    Instance(Regex),
+    Title(Regex),
+    WindowRole(Regex),
+    WindowType(String),
+    ConMark(Regex),
+    ConId(u64),
+    /// `con_id=__focused__`
+    Focused,
+    Workspace(Regex),
+    Name(Regex),
+    Urgent,
+    Floating,
+    Tiling,
+}
+
+impl Criterion {
+    fn parse(key: &str, value: Option<String>) -> Result<Self, String> {
+        let regex = |value: Option<String>| {
+            let value = value.ok_or_else(|| format!("Criterion \"{key}\" needs a value"))?;
+            if value == FOCUSED {
+                return Err(format!(
+                    "\"{key}={FOCUSED}\" is not supported, only \"con_id={FOCUSED}\" is"
+                ));
+            }
+            Regex::new(&value).map_err(|e| format!("Invalid regex for \"{key}\": {e}"))
+        };
+        match key {
+            "class" => Ok(Self::Class(regex(value)?)),
+            "instance" => Ok(Self::Instance(regex(value)?)),
+            "title" => Ok(Self::Title(regex(value)?)),
+            "window_role" => Ok(Self::WindowRole(regex(value)?)),
+            "con_mark" => Ok(Self::ConMark(regex(value)?)),
+            "workspace" => Ok(Self::Workspace(regex(value)?)),
+            // Not an i3 criterion, but containers other than windows only
+            // have a name to be told apart
+            "name" => Ok(Self::Name(regex(value)?)),
+            "window_type" => value
+                .map(Self::WindowType)
+                .ok_or_else(|| String::from("Criterion \"window_type\" needs a value")),
+            "con_id" => match value.as_deref() {
+                None => Err(String::from("Criterion \"con_id\" needs a value")),
+                Some(FOCUSED) => Ok(Self::Focused),
+                Some(id) => id
+                    .parse()
+                    .map(Self::ConId)
+                    .map_err(|e| format!("Invalid container id: {e}")),
+            },
+            // i3 takes `latest` or `oldest` to pick among urgent windows, which
+            // makes no difference when filtering
+            "urgent" => Ok(Self::Urgent),
+            "floating" => Ok(Self::Floating),
+            "tiling" => Ok(Self::Tiling),
+            _ => Err(format!("Unknown criterion \"{key}\"")),
+        }
+    }
+
+    fn matches(&self, node: &Node, workspace: Option<&str>) -> bool {
+        let props = node.window_properties.as_ref();
+        let property = |re: &Regex, get: fn(&WindowProperties) -> Option<&String>| {
+            props.and_then(get).is_some_and(|v| re.is_match(v))
+        };
+        match self {
+            Self::Class(re) => property(re, |p| p.class.as_ref()),
+            Self::Instance(re) => property(re, |p| p.instance.as_ref()),
+            Self::Title(re) => property(re, |p| p.title.as_ref()),
+            Self::WindowRole(re) => property(re, |p| p.window_role.as_ref()),
+            Self::WindowType(ty) => node.window_type.as_ref() == Some(ty),
+            Self::ConMark(re) => node.marks.iter().any(|m| re.is_match(m)),
+            Self::ConId(id) => node.id == Some(*id),
+            Self::Focused => node.focused,
+            Self::Workspace(re) => workspace.is_some_and(|w| re.is_match(w)),
+            Self::Name(re) => node.name.as_ref().is_some_and(|n| re.is_match(n)),
+            Self::Urgent => node.urgent,
+            Self::Floating => node.is_floating(),
+            // Only windows count as tiled, not the containers around them
+            Self::Tiling => props.is_some() && !node.is_floating(),
+        }
+    }
+}
+
+/// A set of criteria, all of which must match
+#[derive(Clone, Debug)]
+pub struct Criteria(Vec<Criterion>);
+
+impl Criteria {
+    /// Checks a node, knowing the name of the workspace it lives on
+    pub fn matches(&self, node: &Node, workspace: Option<&str>) -> bool {
+        self.0.iter().all(|c| c.matches(node, workspace))
+    }
+}
+
+impl FromStr for Criteria {
+    type Err = String;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        let st = st.trim();
+        let st = st.strip_prefix('[').unwrap_or(st);
+        let st = st.strip_suffix(']').unwrap_or(st);
+
+        let mut criteria = Vec::new();
+        let mut chars = st.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let mut key = String::new();
+            while let Some(c) = chars.next_if(|&c| c != '=' && !c.is_whitespace()) {
+                key.push(c);
+            }
+
+            let value = if chars.next_if_eq(&'=').is_some() {
+                let mut value = String::new();
+                if chars.next_if_eq(&'"').is_some() {
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => match chars.next() {
+                                Some('"') => value.push('"'),
+                                Some(c) => {
+                                    value.push('\\');
+                                    value.push(c);
+                                }
+                                None => return Err(String::from("Unterminated criterion value")),
+                            },
+                            Some(c) => value.push(c),
+                            None => return Err(String::from("Unterminated criterion value")),
+                        }
+                    }
+                } else {
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        value.push(c);
+                    }
+                }
+                Some(value)
+            } else {
+                None
+            };
+
+            criteria.push(Criterion::parse(&key, value)?);
+        }
+
+        if criteria.is_empty() {
+            Err(String::from("Empty criteria"))
+        } else {
+            Ok(Self(criteria))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Criteria, Criterion};
+    use crate::{parse_trees, Node};
+
+    fn parse(st: &str) -> Vec<Criterion> {
+        st.parse::<Criteria>().expect("the criteria should parse").0
+    }
+
+    fn parse_error(st: &str) -> String {
+        st.parse::<Criteria>()
+            .expect_err("the criteria should not parse")
+    }
+
+    fn window() -> Node {
+        let window = json!({
+            "id": 42,
+            "type": "con",
+            "name": "Mozilla Firefox",
+            "layout": "splith",
+            "border": "normal",
+            "floating": "auto_off",
+            "percent": 0.5,
+            "marks": ["web"],
+            "focused": true,
+            "window": 1000,
+            "window_type": "normal",
+            "window_properties": {"class": "firefox", "instance": "Navigator", "title": "Say \"hi\" - Mozilla Firefox"},
+        });
+        parse_trees(&window.to_string())
+            .expect("the window should parse")
+            .remove(0)
+    }
+
+    fn matches(st: &str) -> bool {
+        st.parse::<Criteria>()
+            .expect("the criteria should parse")
+            .matches(&window(), Some("1: web"))
+    }
+
+    #[test]
+    fn quoted_and_bare_values() {
+        assert!(matches!(
+            parse(r#"[class="^Fire fox$" instance=Navigator]"#).as_slice(),
+            [Criterion::Class(class), Criterion::Instance(instance)]
+                if class.as_str() == "^Fire fox$" && instance.as_str() == "Navigator"
+        ));
+    }
+
+    #[test]
+    fn escapes() {
+        // Quotes are unescaped, other escapes are left to the regex
+        assert!(matches!(
+            parse(r#"[title="Say \"hi\" \- .*"]"#).as_slice(),
+            [Criterion::Title(title)] if title.as_str() == r#"Say "hi" \- .*"#
+        ));
+    }
+
+    #[test]
+    fn bare_keys_and_brackets() {
+        assert!(matches!(
+            parse("  floating urgent=latest tiling ").as_slice(),
+            [Criterion::Floating, Criterion::Urgent, Criterion::Tiling]
+        ));
+        assert!(matches!(
+            parse("[con_id=42]").as_slice(),
+            [Criterion::ConId(42)]
+        ));
+        assert!(matches!(
+            parse("[con_id=__focused__]").as_slice(),
+            [Criterion::Focused]
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_error("[]"), "Empty criteria");
+        assert_eq!(parse_error("[colour=red]"), "Unknown criterion \"colour\"");
+        assert_eq!(parse_error("[class]"), "Criterion \"class\" needs a value");
+        assert_eq!(
+            parse_error(r#"[class="firefox]"#),
+            "Unterminated criterion value"
+        );
+        assert!(parse_error("[class=(]").starts_with("Invalid regex for \"class\""));
+        assert!(parse_error("[con_id=abc]").starts_with("Invalid container id"));
+        assert_eq!(
+            parse_error("[workspace=__focused__]"),
+            "\"workspace=__focused__\" is not supported, only \"con_id=__focused__\" is"
+        );
+        assert!(parse_error("[class=__focused__]").contains("not supported"));
+    }
+
+    #[test]
+    fn matching() {
+        assert!(matches(r#"[class="^firefox$" con_mark="web"]"#));
+        assert!(matches(r#"[title="\"hi\""]"#));
+        assert!(matches("[instance=Nav workspace=^1 window_type=normal]"));
+        assert!(matches("[con_id=42]"));
+        assert!(matches("[con_id=__focused__]"));
+        assert!(matches("[tiling name=Firefox]"));
+        // Every criterion has to match
+        assert!(!matches(r#"[class="^firefox$" con_mark="mail"]"#));
+        assert!(!matches("[floating]"));
+        assert!(!matches("[urgent]"));
+        assert!(!matches("[workspace=^2]"));
+        assert!(!matches("[window_role=.]"));
+        assert!(!matches("[con_id=7]"));
+    }
+}
@@ -0,0 +1,496 @@
+//! Changes between two trees, as a graph and a changelog
+//!
+//! Containers are matched by con id first, then windows and placeholders by
+//! their class, instance and title, and what is left by its rank among the
+//! children of matching parents. The graph shows the new tree, with the
+//! removed nodes hanging from their old parent.
+
+use std::fmt::Write as _;
+
+use crate::{
+    i18n::Lang, parse_trees, print_graph, read_input, unregex, Node, OutputFormat, Settings,
+};
+
+/// A node of one of the trees, with its place in it
+struct Entry<'a> {
+    node: &'a Node,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Rank of the node among its siblings
+    rank: usize,
+    path: String,
+}
+
+/// Lists the nodes of the trees, parents first
+fn flatten(trees: &[Node]) -> Vec<Entry<'_>> {
+    fn visit<'a>(entries: &mut Vec<Entry<'a>>, node: &'a Node, parent: Option<usize>, rank: usize) {
+        let index = entries.len();
+        let path = parent.map_or_else(
+            || node.path_name(),
+            |p| format!("{} › {}", entries[p].path, node.path_name()),
+        );
+        entries.push(Entry {
+            node,
+            parent,
+            children: Vec::new(),
+            rank,
+            path,
+        });
+        for (rank, child) in node.children().enumerate() {
+            let child_index = entries.len();
+            entries[index].children.push(child_index);
+            visit(entries, child, Some(index), rank);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (rank, tree) in trees.iter().enumerate() {
+        visit(&mut entries, tree, None, rank);
+    }
+    entries
+}
+
+/// What tells a window or a placeholder apart when con ids do not match
+fn properties(node: &Node) -> Option<(Option<String>, Option<String>, Option<String>)> {
+    if !node.is_window() && !node.is_placeholder() {
+        return None;
+    }
+    let (class, instance) = node.application();
+    let title = node.window_properties.as_ref().map_or_else(
+        || node.swallows.get("title").map(|v| unregex(v)),
+        |props| props.title.clone(),
+    );
+    Some((class, instance, title))
+}
+
+/// Size of a node, in pixels when known and in percent of its parent
+/// otherwise
+fn size(node: &Node) -> String {
+    node.rect.as_ref().map_or_else(
+        || format!("{:.1}%", node.percent * 100_f64),
+        |r| format!("{}x{}", r.width, r.height),
+    )
+}
+
+fn layout(node: &Node) -> String {
+    node.layout
+        .as_ref()
+        .map_or_else(|| String::from("-"), ToString::to_string)
+}
+
+/// Whether an old entry matches a new one, knowing the old entry matching
+/// each new entry so far
+type Pass = fn(&Entry, &Entry, &[Option<usize>]) -> bool;
+
+struct Diff<'a> {
+    old: Vec<Entry<'a>>,
+    new: Vec<Entry<'a>>,
+    /// Old entry matching each new entry
+    matches: Vec<Option<usize>>,
+    /// Whether each old entry has a match
+    kept: Vec<bool>,
+}
+
+impl<'a> Diff<'a> {
+    fn new(old: &'a [Node], new: &'a [Node]) -> Self {
+        let old = flatten(old);
+        let new = flatten(new);
+        let mut matches = vec![None; new.len()];
+        let mut kept = vec![false; old.len()];
+        // The last pass also needs the parents to match, which the entries
+        // coming parents first allows
+        let passes: [Pass; 3] = [
+            |o, n, _| o.node.id.is_some() && o.node.id == n.node.id,
+            |o, n, _| properties(o.node).is_some() && properties(o.node) == properties(n.node),
+            |o, n, matches| {
+                let parents_match = match (o.parent, n.parent) {
+                    (None, None) => true,
+                    (Some(o), Some(n)) => matches[n] == Some(o),
+                    _ => false,
+                };
+                parents_match
+                    && o.rank == n.rank
+                    && o.node.tree_type == n.node.tree_type
+                    && properties(o.node).is_some() == properties(n.node).is_some()
+            },
+        ];
+        for pass in passes {
+            for (n, entry) in new.iter().enumerate() {
+                if matches[n].is_some() {
+                    continue;
+                }
+                if let Some(o) =
+                    (0..old.len()).find(|&o| !kept[o] && pass(&old[o], entry, &matches))
+                {
+                    matches[n] = Some(o);
+                    kept[o] = true;
+                }
+            }
+        }
+        Self {
+            old,
+            new,
+            matches,
+            kept,
+        }
+    }
+
+    /// Changes of a new entry, by increasing precedence of their style
+    fn changes(&self, n: usize) -> Vec<&'static str> {
+        let Some(o) = self.matches[n] else {
+            return vec!["added"];
+        };
+        let (old, new) = (self.old[o].node, self.new[n].node);
+        let resized = match (&old.rect, &new.rect) {
+            (Some(a), Some(b)) => a.width != b.width || a.height != b.height,
+            _ => (old.percent - new.percent).abs() > 0.0005,
+        };
+        let parent = self.new[n].parent.and_then(|p| self.matches[p]);
+        [
+            ("resized", resized),
+            ("relayouted", old.layout != new.layout),
+            ("moved", parent != self.old[o].parent),
+        ]
+        .into_iter()
+        .filter_map(|(change, applies)| applies.then_some(change))
+        .collect()
+    }
+
+    /// Number of nodes with each change
+    fn summary(&self, lang: Lang) -> String {
+        let mut counts = ["added", "removed", "moved", "resized", "relayouted"].map(|c| (c, 0));
+        let removed = self.kept.iter().filter(|&&kept| !kept).count();
+        counts[1].1 = removed;
+        for n in 0..self.new.len() {
+            for change in self.changes(n) {
+                if let Some((_, count)) = counts.iter_mut().find(|(c, _)| *c == change) {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+            .iter()
+            .map(|(change, count)| format!("{count} {}", lang.tr(change)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Lines telling what changed, the added and changed nodes first
+    fn changelog(&self, lang: Lang) -> String {
+        let mut log = String::new();
+        for (n, entry) in self.new.iter().enumerate() {
+            let Some(o) = self.matches[n] else {
+                let _ = writeln!(log, "+ {}", entry.path);
+                continue;
+            };
+            let old = &self.old[o];
+            for change in self.changes(n) {
+                let detail = match change {
+                    "moved" => format!(
+                        "{} {}",
+                        lang.tr("moved_from"),
+                        old.parent.map_or("-", |p| self.old[p].path.as_str())
+                    ),
+                    "resized" => format!(
+                        "{} {} → {}",
+                        lang.tr("was_resized"),
+                        size(old.node),
+                        size(entry.node)
+                    ),
+                    _ => format!(
+                        "{} {} → {}",
+                        lang.tr("was_relayouted"),
+                        layout(old.node),
+                        layout(entry.node)
+                    ),
+                };
+                let _ = writeln!(log, "~ {}: {detail}", entry.path);
+            }
+        }
+        for (old, _) in self.old.iter().zip(&self.kept).filter(|(_, &kept)| !kept) {
+            let _ = writeln!(log, "- {}", old.path);
+        }
+        log
+    }
+
+    fn render_node(
+        graph: &mut String,
+        id: &str,
+        node: &Node,
+        changes: &[&str],
+        settings: &Settings,
+    ) {
+        let style = settings.theme.change_style(node, changes);
+        let (shape, minimal_size) = node.shape(settings, &style);
+        let _ = writeln!(
+            graph,
+            "\tnode_{id} [shape={shape} label=\"{label}\"{attrs}{minimal_size}]",
+            label = settings.label_template().render(node, settings),
+            attrs = style.attributes()
+        );
+    }
+
+    fn render_edge(graph: &mut String, from: &str, to: &str, removed: bool, settings: &Settings) {
+        let (from_port, to_port) = settings.edge_ports();
+        let _ = writeln!(
+            graph,
+            "\tnode_{from}{from_port} -> node_{to}{to_port}{}",
+            if removed { " [style=dashed]" } else { "" }
+        );
+    }
+
+    /// Renders a removed node, with the children it lost along with it
+    fn render_removed(&self, graph: &mut String, o: usize, settings: &Settings) {
+        let entry = &self.old[o];
+        Self::render_node(
+            graph,
+            &format!("old_{o}"),
+            entry.node,
+            &["removed"],
+            settings,
+        );
+        for &child in entry.children.iter().filter(|&&c| !self.kept[c]) {
+            self.render_removed(graph, child, settings);
+            Self::render_edge(
+                graph,
+                &format!("old_{o}"),
+                &format!("old_{child}"),
+                true,
+                settings,
+            );
+        }
+    }
+
+    /// Renders a node of the new tree, with the children its match lost
+    fn render_new(&self, graph: &mut String, n: usize, settings: &Settings) {
+        let entry = &self.new[n];
+        Self::render_node(
+            graph,
+            &format!("new_{n}"),
+            entry.node,
+            &self.changes(n),
+            settings,
+        );
+        for &child in &entry.children {
+            self.render_new(graph, child, settings);
+            Self::render_edge(
+                graph,
+                &format!("new_{n}"),
+                &format!("new_{child}"),
+                false,
+                settings,
+            );
+        }
+        if let Some(o) = self.matches[n] {
+            for &child in self.old[o].children.iter().filter(|&&c| !self.kept[c]) {
+                self.render_removed(graph, child, settings);
+                Self::render_edge(
+                    graph,
+                    &format!("new_{n}"),
+                    &format!("old_{child}"),
+                    true,
+                    settings,
+                );
+            }
+        }
+    }
+
+    fn render_dot(&self, settings: &Settings, old: &str, new: &str) -> String {
+        let mut graph = String::from("digraph tuilade {\n");
+        graph.push_str("\tordering=out\n");
+        graph.push_str(&settings.theme.header());
+        if !settings.silent {
+            let _ = writeln!(
+                graph,
+                "\tnode_title[shape=rectangle label = \"{}\\n{} → {}\\n{}\"]",
+                settings.lang.tr("diff"),
+                old.replace('\\', "\\\\").replace('"', "\\\""),
+                new.replace('\\', "\\\\").replace('"', "\\\""),
+                self.summary(settings.lang)
+            );
+        }
+        for n in (0..self.new.len()).filter(|&n| self.new[n].parent.is_none()) {
+            self.render_new(&mut graph, n, settings);
+        }
+        for o in (0..self.old.len()).filter(|&o| self.old[o].parent.is_none() && !self.kept[o]) {
+            self.render_removed(&mut graph, o, settings);
+        }
+        graph.push_str("}\n");
+        graph
+    }
+}
+
+/// Prints the graph of the changes between two tree files, or their
+/// changelog
+pub fn run(old: &str, new: &str, changelog: bool, settings: &Settings) -> Result<(), String> {
+    let old_trees = parse_trees(&read_input(&[old.to_owned()])?)?;
+    let new_trees = parse_trees(&read_input(&[new.to_owned()])?)?;
+    let diff = Diff::new(&old_trees, &new_trees);
+    if changelog {
+        print!("{}", diff.changelog(settings.lang));
+        return Ok(());
+    }
+    let dot = diff.render_dot(settings, old, new);
+    match settings.format {
+        OutputFormat::Dot | OutputFormat::Svg => print_graph(&dot, &settings.format)?,
+        _ => {
+            return Err(String::from(
+                "Diffs can only be rendered as DOT or SVG graphs",
+            ))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::Diff;
+    use crate::{parse_trees, Node};
+
+    /// A container, with a con id when `id` is not zero
+    fn con(id: u64, name: &str, children: &[Value]) -> Value {
+        let mut con = json!({
+            "type": "con",
+            "name": name,
+            "layout": "splith",
+            "border": "normal",
+            "current_border_width": 2,
+            "floating": "auto_off",
+            "percent": null,
+            "marks": [],
+            "nodes": children,
+            "floating_nodes": [],
+        });
+        if id != 0 {
+            con["id"] = json!(id);
+        }
+        con
+    }
+
+    /// A window of the given class, with a con id when `id` is not zero
+    fn window(id: u64, class: &str) -> Value {
+        let mut window = con(id, class, &[]);
+        window["window"] = json!(id + 1000);
+        window["window_properties"] = json!({"class": class, "title": class});
+        window
+    }
+
+    fn trees(tree: &Value) -> Vec<Node> {
+        parse_trees(&tree.to_string()).expect("the tree should parse")
+    }
+
+    /// Names of the old nodes matching each new node
+    fn matches(old: &Value, new: &Value) -> Vec<(String, Option<String>)> {
+        let (old, new) = (trees(old), trees(new));
+        let diff = Diff::new(&old, &new);
+        diff.new
+            .iter()
+            .zip(&diff.matches)
+            .map(|(entry, o)| {
+                (
+                    entry.node.path_name(),
+                    o.map(|o| diff.old[o].node.path_name()),
+                )
+            })
+            .collect()
+    }
+
+    fn pair(new: &str, old: Option<&str>) -> (String, Option<String>) {
+        (new.to_owned(), old.map(str::to_owned))
+    }
+
+    #[test]
+    fn con_ids_come_first() {
+        // Same ids, swapped names and classes
+        let old = con(1, "top", &[window(2, "Firefox"), window(3, "Slack")]);
+        let new = con(1, "top", &[window(2, "Slack"), window(3, "Firefox")]);
+        assert_eq!(
+            matches(&old, &new),
+            [
+                pair("top", Some("top")),
+                pair("Slack", Some("Firefox")),
+                pair("Firefox", Some("Slack")),
+            ]
+        );
+    }
+
+    #[test]
+    fn then_window_properties() {
+        // Layout files have no con ids
+        let old = con(0, "top", &[window(0, "Firefox"), window(0, "Slack")]);
+        let new = con(0, "top", &[window(0, "Slack"), window(0, "Firefox")]);
+        assert_eq!(
+            matches(&old, &new),
+            [
+                pair("top", Some("top")),
+                pair("Slack", Some("Slack")),
+                pair("Firefox", Some("Firefox")),
+            ]
+        );
+    }
+
+    #[test]
+    fn then_rank_under_matching_parents() {
+        let old = con(0, "top", &[con(0, "left", &[]), window(0, "Firefox")]);
+        let new = con(0, "top", &[con(0, "right", &[]), window(0, "Firefox")]);
+        assert_eq!(
+            matches(&old, &new),
+            [
+                pair("top", Some("top")),
+                pair("right", Some("left")),
+                pair("Firefox", Some("Firefox")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unrelated_subtrees_do_not_match_by_rank() {
+        // Windows do not match containers, and the children of unmatched
+        // parents do not match by rank
+        let old = con(
+            0,
+            "top",
+            &[con(0, "a", &[window(0, "Firefox")]), window(0, "Emacs")],
+        );
+        let new = con(
+            0,
+            "top",
+            &[window(0, "Slack"), con(0, "b", &[window(0, "Discord")])],
+        );
+        assert_eq!(
+            matches(&old, &new),
+            [
+                pair("top", Some("top")),
+                pair("Slack", None),
+                pair("b", None),
+                pair("Discord", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes() {
+        let old = con(
+            1,
+            "top",
+            &[window(2, "Firefox"), con(3, "split", &[window(4, "Slack")])],
+        );
+        let mut new = con(
+            1,
+            "top",
+            &[con(3, "split", &[window(2, "Firefox")]), window(5, "Emacs")],
+        );
+        new["nodes"][0]["layout"] = json!("tabbed");
+        let (old, new) = (trees(&old), trees(&new));
+        let diff = Diff::new(&old, &new);
+        let changes: Vec<Vec<&str>> = (0..diff.new.len()).map(|n| diff.changes(n)).collect();
+        assert_eq!(
+            changes,
+            [vec![], vec!["relayouted"], vec!["moved"], vec!["added"]]
+        );
+        // Slack is gone
+        assert_eq!(diff.kept, [true, true, true, false]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Transformations of the trees before rendering
+//!
+//! The trees are pruned down to the containers selected on the command line,
+//! then simplified according to the settings.
+
+use crate::{criteria::Criteria, Layout, Node, Only, Settings, TreeType};
+
+/// Keeps the nodes matching `keep` with their whole subtree, as well as their
+/// ancestors
+///
+/// `keep` is also given the name of the workspace the node lives on, if any.
+fn prune(
+    mut node: Node,
+    workspace: Option<&str>,
+    keep: &dyn Fn(&Node, Option<&str>) -> bool,
+) -> Option<Node> {
+    if keep(&node, workspace) {
+        return Some(node);
+    }
+    let workspace = if node.tree_type == TreeType::Workspace {
+        node.name.clone()
+    } else {
+        workspace.map(str::to_owned)
+    };
+    let prune_all = |children: Vec<Node>| -> Vec<Node> {
+        children
+            .into_iter()
+            .filter_map(|child| prune(child, workspace.as_deref(), keep))
+            .collect()
+    };
+    node.nodes = prune_all(std::mem::take(&mut node.nodes));
+    node.floating_nodes = prune_all(std::mem::take(&mut node.floating_nodes));
+    if node.nodes.is_empty() && node.floating_nodes.is_empty() {
+        None
+    } else {
+        Some(node)
+    }
+}
+
+fn is_selected_output(node: &Node, settings: &Settings) -> bool {
+    node.tree_type == TreeType::Output
+        && node
+            .name
+            .as_ref()
+            .is_some_and(|name| settings.output.iter().any(|p| p.matches(name)))
+}
+
+fn is_selected_workspace(node: &Node, settings: &Settings) -> bool {
+    node.tree_type == TreeType::Workspace
+        && settings.workspace.iter().any(|p| {
+            node.name.as_ref().is_some_and(|name| p.matches(name))
+                || node.num.is_some_and(|num| p.matches(&num.to_string()))
+        })
+}
+
+fn retain(trees: Vec<Node>, keep: &dyn Fn(&Node, Option<&str>) -> bool) -> Vec<Node> {
+    trees
+        .into_iter()
+        .filter_map(|tree| prune(tree, None, keep))
+        .collect()
+}
+
+/// Folds single-child split containers into their child
+fn collapse(mut node: Node) -> Node {
+    node.nodes = std::mem::take(&mut node.nodes)
+        .into_iter()
+        .map(collapse)
+        .collect();
+    node.floating_nodes = std::mem::take(&mut node.floating_nodes)
+        .into_iter()
+        .map(collapse)
+        .collect();
+
+    // Marked or focused containers are kept, as folding them would lose
+    // information, and so are the containers holding workspaces
+    let foldable = node.tree_type == TreeType::Con
+        && node.nodes.len() == 1
+        && node.nodes[0].tree_type == TreeType::Con
+        && matches!(node.layout, Some(Layout::SplitH | Layout::SplitV))
+        && node.marks.is_empty()
+        && !node.focused;
+    match (foldable, node.nodes.pop(), node.layout.take()) {
+        (true, Some(mut child), Some(layout)) => {
+            child.folded.insert(0, layout);
+            child.percent = node.percent;
+            child
+        }
+        (_, child, layout) => {
+            node.nodes.extend(child);
+            node.layout = layout;
+            node
+        }
+    }
+}
+
+/// Removes all children of a node, keeping count of them
+fn hide_children(mut node: Node) -> Node {
+    // The focus now stops here, so that it can still be followed
+    node.focused = node.has_focus();
+    node.hidden = node.descendants();
+    node.nodes.clear();
+    node.floating_nodes.clear();
+    node
+}
+
+fn limit_depth(mut node: Node, depth: usize) -> Node {
+    if depth == 0 {
+        return hide_children(node);
+    }
+    for children in [&mut node.nodes, &mut node.floating_nodes] {
+        *children = std::mem::take(children)
+            .into_iter()
+            .map(|child| limit_depth(child, depth - 1))
+            .collect();
+    }
+    node
+}
+
+/// Keeps the path to the focused window, and collapses its siblings
+///
+/// The focus may lead into a floating container.
+fn focus_path(mut node: Node) -> Node {
+    for children in [&mut node.nodes, &mut node.floating_nodes] {
+        *children = std::mem::take(children)
+            .into_iter()
+            .map(|child| {
+                if child.has_focus() {
+                    focus_path(child)
+                } else {
+                    hide_children(child)
+                }
+            })
+            .collect();
+    }
+    node
+}
+
+/// Flags the nodes matching any of the highlight criteria
+fn highlight(node: &mut Node, workspace: Option<&str>, criteria: &[Criteria]) {
+    node.highlighted = criteria.iter().any(|c| c.matches(node, workspace));
+    let workspace = if node.tree_type == TreeType::Workspace {
+        node.name.clone()
+    } else {
+        workspace.map(str::to_owned)
+    };
+    for child in node.nodes.iter_mut().chain(&mut node.floating_nodes) {
+        highlight(child, workspace.as_deref(), criteria);
+    }
+}
+
+/// Removes the dock areas and the `__i3` output, and splices the `content`
+/// containers of outputs into their parent
+fn hide_internal(mut node: Node) -> Node {
+    let is_output = node.tree_type == TreeType::Output;
+    node.nodes = std::mem::take(&mut node.nodes)
+        .into_iter()
+        .filter(|child| {
+            child.tree_type != TreeType::DockArea
+                && !(child.tree_type == TreeType::Output && child.name.as_deref() == Some("__i3"))
+        })
+        .flat_map(|child| {
+            let child = hide_internal(child);
+            if is_output
+                && child.tree_type == TreeType::Con
+                && child.name.as_deref() == Some("content")
+            {
+                child.nodes
+            } else {
+                vec![child]
+            }
+        })
+        .collect();
+    node.floating_nodes = std::mem::take(&mut node.floating_nodes)
+        .into_iter()
+        .map(hide_internal)
+        .collect();
+    node
+}
+
+/// Prunes and simplifies the trees according to the settings
+pub fn apply(mut trees: Vec<Node>, settings: &Settings) -> Vec<Node> {
+    if settings.hide_internal {
+        trees = trees.into_iter().map(hide_internal).collect();
+    }
+    if !settings.output.is_empty() {
+        trees = retain(trees, &|n, _| is_selected_output(n, settings));
+    }
+    if !settings.workspace.is_empty() {
+        trees = retain(trees, &|n, _| is_selected_workspace(n, settings));
+    }
+    if !settings.criteria.is_empty() {
+        trees = retain(trees, &|n, ws| {
+            settings.criteria.iter().any(|c| c.matches(n, ws))
+        });
+    }
+    match settings.only {
+        Some(Only::Placeholders) => trees = retain(trees, &|n, _| n.is_placeholder()),
+        Some(Only::Windows) => trees = retain(trees, &|n, _| n.is_window()),
+        None => {}
+    }
+    if settings.focus_path {
+        trees = trees.into_iter().map(focus_path).collect();
+    }
+    if settings.collapse_chains {
+        trees = trees.into_iter().map(collapse).collect();
+    }
+    if !settings.highlight.is_empty() {
+        for tree in &mut trees {
+            highlight(tree, None, &settings.highlight);
+        }
+    }
+    if let Some(depth) = settings.max_depth {
+        trees = trees
+            .into_iter()
+            .map(|tree| limit_depth(tree, depth))
+            .collect();
+    }
+    trees
+}
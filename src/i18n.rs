@@ -0,0 +1,131 @@
+//! Message catalogue for the words shown in the graphs
+//!
+//! Every message has a key, which label templates use as `{@key}`, and a
+//! translation in each language.
+
+/// Language of the labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lang {
+    /// English
+    En,
+    /// French
+    Fr,
+    /// German
+    De,
+}
+
+/// Messages by key, in English, French and German
+#[rustfmt::skip]
+const MESSAGES: &[(&str, [&str; 3])] = &[
+    ("tree_type", ["Tree Type:", "Type d'arbre :", "Baumtyp:"]),
+    ("floating", ["Floating:", "Flottant :", "Schwebend:"]),
+    ("border_type", ["Border Type:", "Type de bordure :", "Rahmentyp:"]),
+    ("border_width", ["Border Width:", "Largeur de bordure :", "Rahmenbreite:"]),
+    ("border", ["Border:", "Bordure :", "Rahmen:"]),
+    ("title", ["Title", "Titre", "Titel"]),
+    ("layout", ["Layout:", "Disposition :", "Anordnung:"]),
+    ("folded", ["Folded:", "Replié :", "Eingeklappt:"]),
+    ("geometry", ["Geometry", "Géométrie", "Geometrie"]),
+    ("width", ["Width:", "Largeur :", "Breite:"]),
+    ("height", ["Height:", "Hauteur :", "Höhe:"]),
+    ("percent", ["Percent:", "Pourcentage :", "Prozent:"]),
+    ("not_available", ["N/A", "N/D", "k. A."]),
+    ("swallows", ["Swallows", "Absorbe", "Verschluckt"]),
+    ("marks", ["Marks:", "Marques :", "Markierungen:"]),
+    ("no_marks", ["No marks", "Aucune marque", "Keine Markierungen"]),
+    ("no_name", ["(no name)", "(sans nom)", "(kein Name)"]),
+    ("hidden", ["hidden", "masqués", "ausgeblendet"]),
+    ("auto_on", ["Auto On", "Auto activé", "Automatisch an"]),
+    ("auto_off", ["Auto Off", "Auto désactivé", "Automatisch aus"]),
+    ("user_on", ["User On", "Activé par l'utilisateur", "Vom Benutzer an"]),
+    ("user_off", ["User Off", "Désactivé par l'utilisateur", "Vom Benutzer aus"]),
+    ("viewer", ["Tuilade i3 viewer", "Visionneuse i3 Tuilade", "Tuilade i3-Betrachter"]),
+    ("placeholders", ["placeholders", "emplacements", "Platzhalter"]),
+    ("windows", ["windows", "fenêtres", "Fenster"]),
+    ("scratchpad", ["Tuilade scratchpad", "Scratchpad Tuilade", "Tuilade-Scratchpad"]),
+    ("state", ["State:", "État :", "Zustand:"]),
+    ("shown_on", ["shown on", "affiché sur", "angezeigt auf"]),
+    ("hidden_window", ["hidden", "masqué", "verborgen"]),
+    ("last_shown_on", ["last shown on output", "affiché en dernier sur la sortie", "zuletzt angezeigt auf Ausgang"]),
+    ("con_id", ["Id:", "Id :", "Id:"]),
+    ("window", ["Window:", "Fenêtre :", "Fenster:"]),
+    ("number", ["Number:", "Numéro :", "Nummer:"]),
+    ("rect", ["Rect:", "Rectangle :", "Rechteck:"]),
+    ("urgent", ["Urgent:", "Urgent :", "Dringend:"]),
+    ("yes", ["Yes", "Oui", "Ja"]),
+    ("no", ["No", "Non", "Nein"]),
+    ("class", ["Class:", "Classe :", "Klasse:"]),
+    ("instance", ["Instance:", "Instance :", "Instanz:"]),
+    ("window_title", ["Title:", "Titre :", "Titel:"]),
+    ("window_role", ["Role:", "Rôle :", "Rolle:"]),
+    ("window_type", ["Type:", "Type :", "Typ:"]),
+    ("legend", ["Legend", "Légende", "Legende"]),
+    ("legend_container", ["container", "conteneur", "Container"]),
+    ("legend_window", ["window", "fenêtre", "Fenster"]),
+    ("legend_child", ["child", "enfant", "Kind"]),
+    ("legend_focus", ["focused window", "fenêtre focalisée", "fokussiertes Fenster"]),
+    ("legend_focused", ["focused", "focalisé", "fokussiert"]),
+    ("legend_focus_path", ["holds the focus", "contient le focus", "enthält den Fokus"]),
+    ("legend_marked", ["marked", "marqué", "markiert"]),
+    ("legend_urgent", ["urgent", "urgent", "dringend"]),
+    ("legend_highlighted", ["matches", "correspond", "passt"]),
+    ("legend_highlight_path", ["holds a match", "contient une correspondance", "enthält einen Treffer"]),
+    ("host", ["Host:", "Hôte :", "Rechner:"]),
+    ("date", ["Date:", "Date :", "Datum:"]),
+    ("version", ["Version:", "Version :", "Version:"]),
+    ("sources", ["Sources:", "Sources :", "Quellen:"]),
+    ("stdin", ["standard input", "entrée standard", "Standardeingabe"]),
+    ("whole_tree", ["Whole tree:", "Arbre entier :", "Ganzer Baum:"]),
+    ("outputs", ["outputs", "sorties", "Ausgaben"]),
+    ("workspaces", ["workspaces", "espaces de travail", "Arbeitsflächen"]),
+    ("diff", ["Tuilade diff", "Différences Tuilade", "Tuilade-Unterschiede"]),
+    ("added", ["added", "ajoutés", "hinzugefügt"]),
+    ("removed", ["removed", "supprimés", "entfernt"]),
+    ("moved", ["moved", "déplacés", "verschoben"]),
+    ("resized", ["resized", "redimensionnés", "größenverändert"]),
+    ("relayouted", ["relayouted", "réagencés", "neu angeordnet"]),
+    ("moved_from", ["moved from", "déplacé depuis", "verschoben von"]),
+    ("was_resized", ["resized", "redimensionné", "Größe geändert"]),
+    ("was_relayouted", ["relayouted", "réagencé", "neu angeordnet"]),
+    ("stats_workspaces", ["Windows per workspace", "Fenêtres par espace de travail", "Fenster pro Arbeitsfläche"]),
+    ("stats_outputs", ["Windows per output", "Fenêtres par sortie", "Fenster pro Ausgabe"]),
+    ("stats_layouts", ["Containers per layout", "Conteneurs par disposition", "Container pro Anordnung"]),
+    ("stats_depths", ["Windows and placeholders per depth", "Fenêtres et emplacements par profondeur", "Fenster und Platzhalter pro Tiefe"]),
+    ("stats_marked", ["Marked containers", "Conteneurs marqués", "Markierte Container"]),
+    ("stats_summary", ["Summary", "Résumé", "Zusammenfassung"]),
+    ("stats_workspace", ["Workspace", "Espace de travail", "Arbeitsfläche"]),
+    ("stats_output", ["Output", "Sortie", "Ausgabe"]),
+    ("stats_windows", ["Windows", "Fenêtres", "Fenster"]),
+    ("stats_layout", ["Layout", "Disposition", "Anordnung"]),
+    ("stats_depth", ["Depth", "Profondeur", "Tiefe"]),
+    ("stats_count", ["Count", "Nombre", "Anzahl"]),
+    ("stats_path", ["Path", "Chemin", "Pfad"]),
+    ("stats_marks", ["Marks", "Marques", "Markierungen"]),
+    ("stats_tiling", ["Tiling windows", "Fenêtres en mosaïque", "Gekachelte Fenster"]),
+    ("stats_floating", ["Floating windows", "Fenêtres flottantes", "Schwebende Fenster"]),
+    ("stats_placeholders", ["Placeholders", "Emplacements", "Platzhalter"]),
+    ("stats_deepest", ["Deepest path", "Chemin le plus profond", "Tiefster Pfad"]),
+    ("lint_nested_split", ["split in the same direction as its parent, its children can move up into the parent", "division dans le même sens que son parent, ses enfants peuvent remonter dans le parent", "Teilung in derselben Richtung wie das Elternelement, die Kinder können ins Elternelement wandern"]),
+    ("lint_single_child", ["container wrapping a single child, which can take its place", "conteneur n'enveloppant qu'un enfant, qui peut prendre sa place", "Container mit nur einem Kind, das seinen Platz einnehmen kann"]),
+    ("lint_single_tab", ["tabbed container with a single tab, use a split layout or add tabs", "conteneur à onglets avec un seul onglet, utilisez une division ou ajoutez des onglets", "Container mit nur einem Tab, eine Teilung verwenden oder Tabs hinzufügen"]),
+    ("lint_empty_workspace", ["empty workspace, remove it or put a container in it", "espace de travail vide, supprimez-le ou ajoutez-y un conteneur", "leere Arbeitsfläche, entfernen oder einen Container hinzufügen"]),
+    ("lint_percents", ["percents of the children do not add up to 1", "les pourcentages des enfants ne font pas 1 au total", "die Prozente der Kinder ergeben zusammen nicht 1"]),
+    ("lint_no_swallows", ["leaf without swallows, i3 will never fill it, add criteria to swallow a window", "feuille sans critères d'absorption, i3 ne la remplira jamais, ajoutez des critères", "Blatt ohne Kriterien zum Verschlucken, i3 füllt es nie, Kriterien hinzufügen"]),
+    ("lint_duplicate_mark", ["mark carried by several containers, i3 keeps it on the last one only", "marque portée par plusieurs conteneurs, i3 ne la garde que sur le dernier", "Markierung an mehreren Containern, i3 behält sie nur am letzten"]),
+    ("lint_problems", ["problems found", "problèmes trouvés", "Probleme gefunden"]),
+];
+
+/// Finds the key of a message, as kept by the catalogue
+pub fn key(key: &str) -> Option<&'static str> {
+    MESSAGES.iter().find(|(k, _)| *k == key).map(|(k, _)| *k)
+}
+
+impl Lang {
+    /// Translation of a message
+    pub fn tr(self, key: &str) -> &'static str {
+        MESSAGES.iter().find(|(k, _)| *k == key).map_or_else(
+            || unreachable!("Unknown message \"{key}\""),
+            |(_, texts)| texts[self as usize],
+        )
+    }
+}
@@ -0,0 +1,304 @@
+//! Generation of i3 command scripts recreating a tree
+//!
+//! The script is a shell script sending one command at a time with `i3-msg`,
+//! in order. i3 starts programs without waiting for their windows, while the
+//! commands following them act on the focused container, so the script waits
+//! for each new window before going on. Applications are started from
+//! placeholders guessed from the window class, which are expected to be
+//! edited by hand.
+
+use crate::{text, Layout, Node, TreeType};
+
+/// Prefix of the temporary marks used to resize containers once all of their
+/// siblings exist
+const TEMP_MARK: &str = "_tuilade_";
+
+/// Fallback placeholder when nothing is known about a window
+const DEFAULT_EXEC: &str = "i3-sensible-terminal";
+
+/// Start of the script, with the function starting a program and waiting for
+/// its window, which then holds the focus
+const PRELUDE: &str = r#"#!/bin/sh
+set -e
+
+windows() {
+    i3-msg -t get_tree | grep -o '"window":[0-9]' | wc -l
+}
+
+launch() {
+    before=$(windows)
+    i3-msg -q "exec --no-startup-id $1"
+    tries=0
+    while [ "$(windows)" -le "$before" ]; do
+        tries=$((tries + 1))
+        if [ "$tries" -gt 100 ]; then
+            echo "No window appeared for $1, going on" >&2
+            break
+        fi
+        sleep 0.1
+    done
+}
+
+"#;
+
+struct Script {
+    lines: Vec<String>,
+    temp_marks: usize,
+}
+
+impl Script {
+    /// Adds a command sent to i3
+    fn command(&mut self, command: impl AsRef<str>) {
+        self.lines
+            .push(format!("i3-msg -q {}", shell_quote(command.as_ref())));
+    }
+
+    fn walk(&mut self, node: &Node, top: bool) {
+        match node.tree_type {
+            TreeType::DockArea => {}
+            TreeType::Root | TreeType::Output => {
+                for child in &node.nodes {
+                    self.walk(child, false);
+                }
+            }
+            TreeType::Workspace => self.workspace(node),
+            // Layout files start directly with containers, but in a full tree
+            // the containers found outside of workspaces (like `content`)
+            // only hold more workspaces
+            TreeType::FloatingCon if top => self.floating(node),
+            TreeType::Con | TreeType::FloatingCon => {
+                if top {
+                    self.container(node);
+                } else {
+                    for child in &node.nodes {
+                        self.walk(child, false);
+                    }
+                }
+            }
+        }
+    }
+
+    fn workspace(&mut self, node: &Node) {
+        let name = node.name.as_deref().unwrap_or_default();
+        // Internal workspaces, like the scratchpad, cannot be recreated
+        if name.starts_with("__") {
+            return;
+        }
+        self.command(format!("workspace {}", quote(&text::sanitize(name))));
+        // Empty workspaces have nothing to lay out
+        if let Some(layout) = node
+            .layout
+            .as_ref()
+            .and_then(layout_command)
+            .filter(|_| !node.nodes.is_empty())
+        {
+            self.command(format!("layout {layout}"));
+        }
+        self.children(node, false);
+        for floating in &node.floating_nodes {
+            self.floating(floating);
+        }
+    }
+
+    /// Creates a floating container, at its size and position when known
+    fn floating(&mut self, node: &Node) {
+        match node.nodes.as_slice() {
+            [] => return,
+            [window] => self.container(window),
+            _ => {
+                self.children(node, true);
+                self.command("focus parent");
+            }
+        }
+        self.command("floating enable");
+        if let Some(rect) = &node.rect {
+            self.command(format!("resize set {} px {} px", rect.width, rect.height));
+            self.command(format!(
+                "move absolute position {} px {} px",
+                rect.x, rect.y
+            ));
+        }
+        for mark in &node.marks {
+            self.command(format!("mark --add {}", quote(&text::sanitize(mark))));
+        }
+    }
+
+    /// Creates a container and its children, leaving it focused
+    fn container(&mut self, node: &Node) {
+        if node.nodes.is_empty() {
+            self.exec(node);
+        } else {
+            self.children(node, true);
+            self.command("focus parent");
+        }
+        for mark in &node.marks {
+            self.command(format!("mark --add {}", quote(&text::sanitize(mark))));
+        }
+    }
+
+    fn children(&mut self, parent: &Node, split: bool) {
+        let dimension = match parent.layout {
+            Some(Layout::SplitH) => Some("width"),
+            Some(Layout::SplitV) => Some("height"),
+            _ => None,
+        }
+        .filter(|_| parent.nodes.len() > 1);
+
+        let mut sizes = Vec::new();
+        for (pos, child) in parent.nodes.iter().enumerate() {
+            self.container(child);
+            if dimension.is_some() && child.percent > 0.0 {
+                let mark = format!("{TEMP_MARK}{}", self.temp_marks);
+                self.temp_marks += 1;
+                self.command(format!("mark --add {mark}"));
+                sizes.push((mark, child.percent));
+            }
+            // The first child is wrapped in a new container, and every
+            // following sibling will open next to it
+            if split && pos == 0 {
+                self.split(parent.layout.as_ref());
+            }
+        }
+
+        // Resizing is only meaningful once all siblings are there
+        if let Some(dimension) = dimension {
+            for (mark, percent) in sizes {
+                self.command(format!(
+                    "[con_mark=\"^{mark}$\"] resize set {dimension} {:.0} ppt",
+                    percent * 100_f64
+                ));
+                self.command(format!("unmark {mark}"));
+            }
+        }
+    }
+
+    fn split(&mut self, layout: Option<&Layout>) {
+        match layout {
+            Some(Layout::SplitH) => self.command("split h"),
+            Some(layout) => {
+                self.command("split v");
+                if let Some(command) = layout_command(layout).filter(|&c| c != "splitv") {
+                    self.command(format!("layout {command}"));
+                }
+            }
+            None => self.command("split v"),
+        }
+    }
+
+    fn exec(&mut self, node: &Node) {
+        // Windows pick their own class, which must not break out of the line
+        let (class, instance) = node.application();
+        let (class, instance) = (
+            class.as_deref().map(text::sanitize),
+            instance.as_deref().map(text::sanitize),
+        );
+
+        self.lines.push(format!(
+            "# {name} (class: {class}, instance: {instance})",
+            name = text::sanitize(node.name.as_deref().unwrap_or("(no name)")),
+            class = class.as_deref().unwrap_or("?"),
+            instance = instance.as_deref().unwrap_or("?"),
+        ));
+        let program = class
+            .or(instance)
+            .map(|p| p.to_lowercase())
+            .filter(|p| is_program_name(p))
+            .unwrap_or_else(|| DEFAULT_EXEC.to_owned());
+        self.lines.push(format!("launch {program}"));
+    }
+}
+
+/// Name of a layout for the `layout` command, if it can be set
+const fn layout_command(layout: &Layout) -> Option<&'static str> {
+    match layout {
+        Layout::SplitH => Some("splith"),
+        Layout::SplitV => Some("splitv"),
+        Layout::Tabbed => Some("tabbed"),
+        Layout::Stacked => Some("stacking"),
+        Layout::Output | Layout::DockArea => None,
+    }
+}
+
+/// Whether a guessed program name is a single plain word, safe to run
+fn is_program_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn quote(st: &str) -> String {
+    format!("\"{}\"", st.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quotes a word for the shell, which expands nothing between single quotes
+fn shell_quote(st: &str) -> String {
+    format!("'{}'", st.replace('\'', "'\\''"))
+}
+
+/// Builds the script of commands recreating the given trees
+pub fn generate(trees: &[Node]) -> String {
+    let mut script = Script {
+        lines: Vec::new(),
+        temp_marks: 0,
+    };
+    for tree in trees {
+        script.walk(tree, true);
+    }
+    script
+        .lines
+        .into_iter()
+        .fold(String::from(PRELUDE), |script, line| script + &line + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use crate::parse_trees;
+
+    /// Compares the script generated from a tree with its golden script
+    fn check(tree: &str, golden: &str) {
+        let trees = parse_trees(tree).expect("the tree should parse");
+        assert_eq!(generate(&trees), golden);
+    }
+
+    #[test]
+    fn full_tree() {
+        check(
+            include_str!("../tests/golden/i3cmd/full_tree.json"),
+            include_str!("../tests/golden/i3cmd/full_tree.sh"),
+        );
+    }
+
+    #[test]
+    fn layout_with_swallows() {
+        check(
+            include_str!("../tests/golden/i3cmd/layout_swallows.json"),
+            include_str!("../tests/golden/i3cmd/layout_swallows.sh"),
+        );
+    }
+
+    #[test]
+    fn nested_splits_and_tabs() {
+        check(
+            include_str!("../tests/golden/i3cmd/nested.json"),
+            include_str!("../tests/golden/i3cmd/nested.sh"),
+        );
+    }
+
+    #[test]
+    fn marks() {
+        check(
+            include_str!("../tests/golden/i3cmd/marks.json"),
+            include_str!("../tests/golden/i3cmd/marks.sh"),
+        );
+    }
+
+    #[test]
+    fn floating_windows() {
+        check(
+            include_str!("../tests/golden/i3cmd/floating.json"),
+            include_str!("../tests/golden/i3cmd/floating.sh"),
+        );
+    }
+}
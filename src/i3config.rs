@@ -0,0 +1,176 @@
+//! Window colors read from an i3 configuration file
+//!
+//! Only the `client.<state>` lines are read, after substituting the
+//! variables defined with `set` (or `set_from_resource`, using its fallback).
+
+use std::collections::HashMap;
+
+/// States of the windows that have their own colors
+const STATES: &[&str] = &[
+    "focused",
+    "focused_inactive",
+    "unfocused",
+    "urgent",
+    "placeholder",
+];
+
+/// Colors of the windows in one state
+#[derive(Clone, Debug)]
+pub struct ClientColors {
+    pub border: String,
+    pub background: String,
+    pub text: String,
+}
+
+/// Client colors, by state
+#[derive(Clone, Debug)]
+pub struct Colors(pub HashMap<String, ClientColors>);
+
+/// Substitutes the variables in a line, longest names first so that `$bg`
+/// does not eat into `$bg_urgent`
+fn substitute(line: &str, variables: &[(String, String)]) -> String {
+    variables
+        .iter()
+        .fold(line.to_owned(), |line, (name, value)| {
+            line.replace(name, value)
+        })
+}
+
+/// Reads the client colors of an i3 configuration file
+pub fn load(path: &str) -> Result<Colors, String> {
+    let config = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read i3 config \"{path}\": {e}"))?;
+    parse(&config)
+}
+
+/// Parses the client colors of the text of an i3 configuration
+fn parse(config: &str) -> Result<Colors, String> {
+    let lines: Vec<&str> = config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    // Like i3, variables apply to the whole file wherever they are set
+    let mut variables: Vec<(String, String)> = lines
+        .iter()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match words.next()? {
+                "set" => {
+                    let name = words.next()?;
+                    Some((name.to_owned(), words.collect::<Vec<_>>().join(" ")))
+                }
+                "set_from_resource" => {
+                    let name = words.next()?;
+                    let _resource = words.next()?;
+                    Some((name.to_owned(), words.collect::<Vec<_>>().join(" ")))
+                }
+                _ => None,
+            }
+        })
+        .filter(|(name, _)| name.starts_with('$'))
+        .collect();
+    variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut colors = HashMap::new();
+    for line in lines {
+        let Some(state) = line
+            .strip_prefix("client.")
+            .and_then(|rest| rest.split_whitespace().next())
+            .filter(|state| STATES.contains(state))
+        else {
+            continue;
+        };
+        let line = substitute(line, &variables);
+        let values: Vec<&str> = line.split_whitespace().skip(1).collect();
+        let [border, background, text, ..] = values[..] else {
+            return Err(format!(
+                "\"client.{state}\" needs a border, a background and a text color"
+            ));
+        };
+        colors.insert(
+            state.to_owned(),
+            ClientColors {
+                border: border.to_owned(),
+                background: background.to_owned(),
+                text: text.to_owned(),
+            },
+        );
+    }
+    Ok(Colors(colors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Colors};
+
+    fn client(colors: &Colors, state: &str) -> (String, String, String) {
+        let client = &colors.0[state];
+        (
+            client.border.clone(),
+            client.background.clone(),
+            client.text.clone(),
+        )
+    }
+
+    fn triple(border: &str, background: &str, text: &str) -> (String, String, String) {
+        (border.to_owned(), background.to_owned(), text.to_owned())
+    }
+
+    #[test]
+    fn client_colors() {
+        let config = "
+            # class                 border  backgr. text    indicator child_border
+            client.focused          #4c7899 #285577 #ffffff #2e9ef4   #285577
+            client.unfocused        #333333 #222222 #888888
+            client.background       #ffffff
+            bindsym $mod+Return exec i3-sensible-terminal
+        ";
+        let colors = parse(config).expect("the config should parse");
+        assert_eq!(colors.0.len(), 2);
+        assert_eq!(
+            client(&colors, "focused"),
+            triple("#4c7899", "#285577", "#ffffff")
+        );
+        assert_eq!(
+            client(&colors, "unfocused"),
+            triple("#333333", "#222222", "#888888")
+        );
+    }
+
+    #[test]
+    fn variables_longest_first() {
+        // Set after their use, and with `$bg` a prefix of `$bg_urgent`
+        let config = "
+            client.urgent $bg_urgent $bg $fg
+            client.placeholder $bg $bg_urgent $fg
+            set $bg #222222
+            set $bg_urgent #900000
+            set_from_resource $fg i3wm.foreground #eeeeee
+        ";
+        let colors = parse(config).expect("the config should parse");
+        assert_eq!(
+            client(&colors, "urgent"),
+            triple("#900000", "#222222", "#eeeeee")
+        );
+        assert_eq!(
+            client(&colors, "placeholder"),
+            triple("#222222", "#900000", "#eeeeee")
+        );
+    }
+
+    #[test]
+    fn missing_colors() {
+        assert_eq!(
+            parse("client.focused #4c7899 #285577").expect_err("two colors are not enough"),
+            "\"client.focused\" needs a border, a background and a text color"
+        );
+        // An undefined variable is left as is, but still counts as a color
+        assert!(parse("client.focused $undefined #285577 #ffffff").is_ok());
+        assert!(parse("# no colors")
+            .expect("the config should parse")
+            .0
+            .is_empty());
+    }
+}
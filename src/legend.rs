@@ -0,0 +1,102 @@
+//! Cluster explaining the shapes, colors and edges of the graph
+//!
+//! The legend only lists what the theme sets apart, so the plain theme gets
+//! a short one.
+
+use std::fmt::Write as _;
+
+use crate::{Density, Layout, Settings};
+
+/// Escapes a plain label, keeping its lines
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Subgraph of the legend, its nodes stacked by invisible edges
+pub fn render(settings: &Settings) -> String {
+    let lang = settings.lang;
+    let theme = &settings.theme;
+    let highlighting = !settings.highlight.is_empty();
+
+    let mut nodes: Vec<String> = Vec::new();
+    let mut push = |shape: &str, label: &str, attrs: &str| {
+        nodes.push(format!("shape={shape} label=\"{}\"{attrs}", escape(label)));
+    };
+
+    // Shapes
+    if settings.density() == Density::Minimal {
+        push(
+            "ellipse",
+            lang.tr("legend_window"),
+            " height=0.3 fontsize=10",
+        );
+        let glyphs = [
+            Layout::SplitH,
+            Layout::SplitV,
+            Layout::Tabbed,
+            Layout::Stacked,
+            Layout::Output,
+            Layout::DockArea,
+        ]
+        .iter()
+        .map(|layout| format!("{} {layout}", layout.glyph()))
+        .collect::<Vec<_>>()
+        .join("\n");
+        push(
+            "box",
+            &format!("{}\n{glyphs}", lang.tr("legend_container")),
+            "",
+        );
+    } else {
+        push("box", lang.tr("legend_container"), "");
+    }
+    push(
+        "box",
+        &format!("{}{}", theme.focus_marker, lang.tr("legend_focus")),
+        "",
+    );
+
+    // Colors
+    for (label, style) in theme.legend_nodes(lang, highlighting) {
+        let shape = match style.shape() {
+            // Records would need fields
+            "record" | "Mrecord" => "box",
+            shape => shape,
+        };
+        push(shape, &label, &style.attributes());
+    }
+
+    let mut legend = format!(
+        "\tsubgraph cluster_legend {{\n\t\tlabel=\"{}\"\n",
+        lang.tr("legend")
+    );
+    for (pos, attrs) in nodes.iter().enumerate() {
+        let _ = writeln!(legend, "\t\tnode_legend_{pos} [{attrs}]");
+    }
+    if nodes.len() > 1 {
+        let chain = (0..nodes.len())
+            .map(|pos| format!("node_legend_{pos}"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        let _ = writeln!(legend, "\t\t{chain} [style=invis]");
+    }
+
+    // Edges, between points
+    let child = [(lang.tr("legend_child").to_owned(), String::new())];
+    let edges = theme
+        .legend_edges(lang, highlighting)
+        .into_iter()
+        .map(|(label, style)| (label, style.attributes()));
+    for (pos, (label, attrs)) in child.into_iter().chain(edges).enumerate() {
+        let _ = writeln!(
+            legend,
+            "\t\tnode_legend_edge_{pos}_from [shape=point]\n\t\tnode_legend_edge_{pos}_to [shape=point]\n\t\tnode_legend_edge_{pos}_from -> node_legend_edge_{pos}_to [label=\"{}\"{attrs}]",
+            escape(&label)
+        );
+    }
+    legend.push_str("\t}\n");
+    legend
+}
@@ -0,0 +1,358 @@
+//! Checks of the trees for containers i3 would rather not have
+//!
+//! The problems are reported with the path of the node they concern, so that
+//! they can be fixed in the layout files, and make the command fail. Each
+//! node gets a single problem, the first found in the order of the checks.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{i18n::Lang, parse_trees, read_input, Layout, Node, Settings, TreeType};
+
+/// Threshold above which the percents of siblings are off
+const PERCENT_TOLERANCE: f64 = 0.01;
+
+struct Problem {
+    path: String,
+    /// Key of the message explaining the problem
+    message: &'static str,
+    detail: Option<String>,
+}
+
+impl Problem {
+    fn describe(&self, lang: Lang) -> String {
+        format!(
+            "{}: {}{}",
+            self.path,
+            lang.tr(self.message),
+            self.detail.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    problems: Vec<Problem>,
+    /// Rank of the next node visited
+    visited: usize,
+    /// Containers carrying each mark
+    marks: HashMap<String, Vec<Marked>>,
+}
+
+/// A container carrying a mark
+struct Marked {
+    /// Rank of the container among the visited nodes
+    rank: usize,
+    path: String,
+    /// Whether the container already has a problem
+    has_problem: bool,
+}
+
+const fn is_split(layout: Option<&Layout>) -> bool {
+    matches!(layout, Some(Layout::SplitH | Layout::SplitV))
+}
+
+impl Linter {
+    fn report(&mut self, path: &str, message: &'static str, detail: Option<String>) {
+        self.problems.push(Problem {
+            path: path.to_owned(),
+            message,
+            detail,
+        });
+    }
+
+    /// The first problem of a node, with its detail
+    fn check(
+        node: &Node,
+        parent: Option<&Node>,
+        in_workspace: bool,
+    ) -> Option<(&'static str, Option<String>)> {
+        let is_con = node.tree_type == TreeType::Con;
+        let layout = node.layout.as_ref();
+        // Outputs and workspaces have no percent, nor do some children
+        let percents = (!node.nodes.is_empty()
+            && node.nodes.iter().all(|n| n.tree_type == TreeType::Con)
+            && node.nodes.iter().all(|n| n.percent > 0_f64))
+        .then(|| node.nodes.iter().map(|n| n.percent).sum::<f64>())
+        .filter(|sum| (sum - 1_f64).abs() > PERCENT_TOLERANCE);
+
+        if in_workspace && is_con && is_split(layout) && node.nodes.len() == 1 {
+            Some(("lint_single_child", None))
+        } else if layout == Some(&Layout::Tabbed) && node.nodes.len() == 1 {
+            Some(("lint_single_tab", None))
+        } else if is_con
+            && is_split(layout)
+            && parent.is_some_and(|p| {
+                matches!(p.tree_type, TreeType::Con | TreeType::Workspace)
+                    && p.layout.as_ref() == layout
+            })
+            && !node.nodes.is_empty()
+        {
+            Some(("lint_nested_split", None))
+        } else if node.tree_type == TreeType::Workspace
+            && node.nodes.is_empty()
+            && node.floating_nodes.is_empty()
+            && !node.is_internal()
+        {
+            Some(("lint_empty_workspace", None))
+        } else if let Some(sum) = percents {
+            Some(("lint_percents", Some(format!(" ({sum:.3})"))))
+        } else if node.id.is_none() && is_con && node.is_leaf() && node.swallows.is_empty() {
+            // Only layout files lack container ids
+            Some(("lint_no_swallows", None))
+        } else {
+            None
+        }
+    }
+
+    /// Checks a node and its subtree
+    fn visit(&mut self, node: &Node, parent: Option<&Node>, in_workspace: bool, path: &str) {
+        let problem = Self::check(node, parent, in_workspace);
+        let has_problem = problem.is_some();
+        let rank = self.visited;
+        self.visited += 1;
+        if let Some((message, detail)) = problem {
+            self.report(path, message, detail);
+        }
+        for mark in &node.marks {
+            self.marks.entry(mark.clone()).or_default().push(Marked {
+                rank,
+                path: path.to_owned(),
+                has_problem,
+            });
+        }
+
+        let inside = in_workspace || node.tree_type == TreeType::Workspace;
+        for child in node.nodes.iter().chain(&node.floating_nodes) {
+            let child_path = format!("{path} › {}", child.path_name());
+            self.visit(child, Some(node), inside, &child_path);
+        }
+    }
+
+    /// Reports the marks carried by several containers, once per container
+    /// without a problem yet
+    fn check_marks(&mut self) {
+        let mut marks: Vec<(String, Vec<Marked>)> = std::mem::take(&mut self.marks)
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .collect();
+        marks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut reported = HashSet::new();
+        for (mark, paths) in marks {
+            for marked in paths {
+                if !marked.has_problem && reported.insert(marked.rank) {
+                    self.report(
+                        &marked.path,
+                        "lint_duplicate_mark",
+                        Some(format!(" (\"{mark}\")")),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Problems of the trees, in the order of their nodes, the duplicate marks
+/// last
+fn problems(trees: &[Node]) -> Vec<Problem> {
+    let mut linter = Linter::default();
+    for tree in trees {
+        // Layout files start below a workspace
+        let in_workspace = tree.tree_type == TreeType::Con;
+        linter.visit(tree, None, in_workspace, &tree.path_name());
+    }
+    linter.check_marks();
+    linter.problems
+}
+
+/// Prints the problems of the trees read from `files`, or from the standard
+/// input, failing if there are any
+pub fn run(files: &[String], settings: &Settings) -> Result<(), String> {
+    let code = read_input(files)?;
+    if code.is_empty() {
+        return Ok(());
+    }
+    let problems = problems(&parse_trees(&code)?);
+    for problem in &problems {
+        println!("{}", problem.describe(settings.lang));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {}",
+            problems.len(),
+            settings.lang.tr("lint_problems")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::problems;
+    use crate::parse_trees;
+
+    /// A container of a layout file, its leaves waiting for a window
+    fn con(layout: &str, percent: f64, children: &[Value]) -> Value {
+        json!({
+            "type": "con",
+            "layout": layout,
+            "border": "normal",
+            "floating": "auto_off",
+            "percent": percent,
+            "marks": [],
+            "nodes": children,
+            "swallows": if children.is_empty() { json!([{"class": "^URxvt$"}]) } else { json!([]) },
+        })
+    }
+
+    fn leaf() -> Value {
+        con("splith", 0.5, &[])
+    }
+
+    fn workspace(name: &str, children: &[Value]) -> Value {
+        json!({
+            "type": "workspace",
+            "name": name,
+            "layout": "splith",
+            "border": "normal",
+            "floating": "auto_off",
+            "percent": null,
+            "marks": [],
+            "nodes": children,
+        })
+    }
+
+    fn with(mut node: Value, key: &str, value: Value) -> Value {
+        node[key] = value;
+        node
+    }
+
+    /// Paths and message keys of the problems of a tree
+    fn lint(tree: &Value) -> Vec<(String, &'static str)> {
+        problems(&parse_trees(&tree.to_string()).expect("the tree should parse"))
+            .into_iter()
+            .map(|problem| (problem.path, problem.message))
+            .collect()
+    }
+
+    #[test]
+    fn rules() {
+        let cases = [
+            ("clean", con("splith", 1.0, &[leaf(), leaf()]), vec![]),
+            (
+                "nested split",
+                con(
+                    "splith",
+                    1.0,
+                    &[con("splith", 0.5, &[leaf(), leaf()]), leaf()],
+                ),
+                vec![("con (splith) › con (splith)", "lint_nested_split")],
+            ),
+            (
+                "single child",
+                con(
+                    "splitv",
+                    1.0,
+                    &[
+                        con("splith", 0.5, &[with(leaf(), "percent", json!(1.0))]),
+                        leaf(),
+                    ],
+                ),
+                vec![("con (splitv) › con (splith)", "lint_single_child")],
+            ),
+            (
+                "single tab",
+                con(
+                    "splith",
+                    1.0,
+                    &[
+                        con("tabbed", 0.5, &[with(leaf(), "percent", json!(1.0))]),
+                        leaf(),
+                    ],
+                ),
+                vec![("con (splith) › con (tabbed)", "lint_single_tab")],
+            ),
+            (
+                "empty workspace",
+                json!({
+                    "type": "root",
+                    "name": "root",
+                    "layout": "splith",
+                    "border": "normal",
+                    "floating": "auto_off",
+                    "percent": null,
+                    "marks": [],
+                    "nodes": [workspace("1", &[]), workspace("__i3_scratch", &[])],
+                }),
+                vec![("root › 1", "lint_empty_workspace")],
+            ),
+            (
+                "percents",
+                con(
+                    "splith",
+                    1.0,
+                    &[leaf(), with(leaf(), "percent", json!(0.3))],
+                ),
+                vec![("con (splith)", "lint_percents")],
+            ),
+            (
+                "no swallows",
+                con(
+                    "splith",
+                    1.0,
+                    &[leaf(), with(leaf(), "swallows", json!([]))],
+                ),
+                vec![("con (splith) › con (splith)", "lint_no_swallows")],
+            ),
+            (
+                "duplicate marks",
+                con(
+                    "splith",
+                    1.0,
+                    &[
+                        with(leaf(), "marks", json!(["web"])),
+                        with(leaf(), "marks", json!(["web"])),
+                    ],
+                ),
+                vec![
+                    ("con (splith) › con (splith)", "lint_duplicate_mark"),
+                    ("con (splith) › con (splith)", "lint_duplicate_mark"),
+                ],
+            ),
+        ];
+        for (name, tree, expected) in cases {
+            let expected: Vec<(String, &str)> = expected
+                .into_iter()
+                .map(|(path, message)| (path.to_owned(), message))
+                .collect();
+            assert_eq!(lint(&tree), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn one_problem_per_node() {
+        // A wrapper with a single child, nested in a split of the same
+        // direction, and carrying two duplicate marks
+        let wrapper = with(
+            con("splith", 0.5, &[with(leaf(), "percent", json!(1.0))]),
+            "marks",
+            json!(["a", "b"]),
+        );
+        let marked = with(leaf(), "marks", json!(["a", "b"]));
+        assert_eq!(
+            lint(&con("splith", 1.0, &[wrapper, marked])),
+            [
+                (
+                    String::from("con (splith) › con (splith)"),
+                    "lint_single_child"
+                ),
+                (
+                    String::from("con (splith) › con (splith)"),
+                    "lint_duplicate_mark"
+                ),
+            ]
+        );
+    }
+}
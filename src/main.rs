@@ -20,25 +20,253 @@
 use clap::Parser;
 use serde_derive::Deserialize;
 use serde_json::Value;
-use std::{collections::HashMap, convert::TryFrom, io::prelude::*};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::{self, Write as _},
+    io::prelude::*,
+};
 
+mod anchor;
+mod appmap;
+mod criteria;
+mod diff;
+mod filter;
+mod i18n;
+mod i3cmd;
+mod i3config;
+mod legend;
+mod lint;
+mod markdown;
+mod metadata;
+mod scale;
+mod scratchpad;
+mod stats;
+#[cfg(feature = "svg")]
+mod svg;
+mod template;
+mod text;
+mod theme;
 mod utils;
 
+// Reasoning: Most settings are simple on/off command line flags
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Settings {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Read the trees from these files instead of the standard input
+    pub files: Vec<String>,
     /// If enabled, will hide empty sections at best
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     pub silent: bool,
     /// If enabled, will hide swallows
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     pub no_swallows: bool,
-    /// Expand tree from a given level
-    #[arg(short, long, default_value_t = TreeType::Workspace)]
-    pub expand_from: TreeType,
+    /// Expand the tree from these anchors: a tree type, `mark:<MARK>`,
+    /// `id:<CON_ID>` or `name:<GLOB>`
+    #[arg(short, long, default_value = "workspace")]
+    pub expand_from: Vec<anchor::Anchor>,
     /// Show the parents in the tree
     #[arg(short, long, default_value_t = false)]
     pub print_parents: bool,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Dot, global = true)]
+    pub format: OutputFormat,
+    /// Wrap workspaces in collapsible sections in Markdown output
+    #[arg(long, default_value_t = false)]
+    pub details: bool,
+    /// Only show the workspaces with these names or numbers (accepts globs)
+    #[arg(short, long)]
+    pub workspace: Vec<glob::Pattern>,
+    /// Only show the outputs with these names (accepts globs)
+    #[arg(short, long)]
+    pub output: Vec<glob::Pattern>,
+    /// Only show the containers matching these i3 criteria, like
+    /// `[class="^Firefox$" con_mark="web"]`
+    #[arg(short = 'm', long = "match")]
+    pub criteria: Vec<criteria::Criteria>,
+    /// Hide the nodes deeper than this level, the root being at level 0
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+    /// Fold split containers holding a single child into that child
+    #[arg(long, default_value_t = false)]
+    pub collapse_chains: bool,
+    /// Only show the path to the focused window, with the siblings at each level
+    #[arg(long, default_value_t = false)]
+    pub focus_path: bool,
+    /// Highlight the containers matching these i3 criteria, without hiding anything
+    #[arg(long)]
+    pub highlight: Vec<criteria::Criteria>,
+    /// List the scratchpad windows instead of the tree, with the output hidden ones were last shown on (the filters do not apply)
+    #[arg(long, default_value_t = false)]
+    pub scratchpad: bool,
+    /// Hide the dock areas, the `__i3` output and the `content` containers
+    #[arg(long, default_value_t = false)]
+    pub hide_internal: bool,
+    /// Only show the placeholders of a layout file, or the actual windows
+    #[arg(long, value_enum)]
+    pub only: Option<Only>,
+    /// Lay out the children of horizontal splits side by side, and those of
+    /// vertical splits from top to bottom
+    #[arg(long, global = true)]
+    pub arrange: bool,
+    /// Size the nodes after the share of their workspace they take
+    #[arg(long, value_enum)]
+    pub scale: Option<scale::Scale>,
+    /// What grows with the share of the workspace
+    #[arg(long, value_enum, default_value = "size")]
+    pub scale_target: scale::ScaleTarget,
+    /// Show the windows with the glyphs and colors of this application map file
+    #[arg(long, value_parser = appmap::load, global = true)]
+    pub app_map: Option<appmap::AppMap>,
+    /// Add a legend explaining the shapes, colors and edges of the graph
+    #[arg(long)]
+    pub legend: bool,
+    /// Stamp the header with the host, the date and the version of the running window manager, for trees taken live
+    #[arg(long)]
+    pub stamp: bool,
+    /// Language of the labels
+    #[arg(long, value_enum, default_value = "en", global = true)]
+    pub lang: i18n::Lang,
+    /// Level of detail of the nodes [default: compact in silent mode, normal
+    /// otherwise]
+    #[arg(long, value_enum, global = true)]
+    pub density: Option<Density>,
+    /// Theme styling the graph: `plain`, `light`, `dark`, `colorblind` or the
+    /// path to a TOML theme file
+    #[arg(long, default_value = "plain", value_parser = theme::Theme::load, global = true)]
+    pub theme: theme::Theme,
+    /// Take the window colors of this i3 configuration file
+    #[arg(long, value_parser = i3config::load, global = true)]
+    pub i3_config: Option<i3config::Colors>,
+    /// Template of the node labels, like `{name|trunc:40} | {layout} | {percent:%.1}`
+    #[arg(long, global = true)]
+    pub label_template: Option<template::Template>,
+    /// Cut the names wider than this many columns
+    #[arg(long, default_value_t = 50, global = true)]
+    pub max_width: usize,
+    /// Where to cut the names that are too wide
+    #[arg(long, value_enum, default_value = "end", global = true)]
+    pub ellipsis: text::Ellipsis,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Render the changes between two trees, or list them
+    Diff {
+        /// The tree before the changes
+        old: String,
+        /// The tree after the changes
+        new: String,
+        /// Print the list of changes instead of the graph
+        #[arg(long)]
+        changelog: bool,
+    },
+    /// Print statistics of the trees: windows per workspace and output,
+    /// layouts, depths, marks and placeholders
+    Stats {
+        /// Read the trees from these files instead of the standard input
+        files: Vec<String>,
+        /// Print the statistics as JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the trees for redundant containers, odd percents, leaves
+    /// without swallows and duplicate marks, failing if any is found
+    Lint {
+        /// Read the trees from these files instead of the standard input
+        files: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum Only {
+    /// Leaves waiting to swallow a window
+    Placeholders,
+    /// Actual windows
+    Windows,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Density {
+    /// Leaves as small ellipses, containers as the glyph of their layout
+    Minimal,
+    /// Records without their empty or default fields
+    Compact,
+    /// Records with the main fields
+    Normal,
+    /// Records with every parsed field
+    Full,
+}
+
+impl Settings {
+    /// Whether the whole subtree of a node is shown
+    fn is_anchor(&self, node: &Node) -> bool {
+        self.expand_from.iter().any(|a| a.matches(node))
+    }
+
+    /// Whether a node holds a specific anchor, in which case it is shown even
+    /// away from the focus
+    fn leads_to_anchor(&self, node: &Node) -> bool {
+        self.expand_from
+            .iter()
+            .any(|a| a.is_specific() && a.matches(node))
+            || node.children().any(|n| self.leads_to_anchor(n))
+    }
+
+    /// Whether the tree is pruned down to a selection before rendering
+    const fn is_filtering(&self) -> bool {
+        !self.workspace.is_empty()
+            || !self.output.is_empty()
+            || !self.criteria.is_empty()
+            || self.focus_path
+            || self.only.is_some()
+    }
+
+    /// Template of the node labels, be it given or the default one
+    fn label_template(&self) -> &template::Template {
+        self.label_template
+            .as_ref()
+            .unwrap_or_else(|| match self.density() {
+                Density::Minimal => &template::MINIMAL,
+                Density::Compact => &template::COMPACT,
+                Density::Normal => &template::DEFAULT,
+                Density::Full => &template::FULL,
+            })
+    }
+
+    /// Level of detail of the nodes, silent mode making them compact
+    fn density(&self) -> Density {
+        self.density.unwrap_or(if self.silent {
+            Density::Compact
+        } else {
+            Density::Normal
+        })
+    }
+
+    /// Ports the edges leave from and point to
+    fn edge_ports(&self) -> (&'static str, &'static str) {
+        // Minimal nodes have no fields, hence no ports for the edges
+        if self.density() == Density::Minimal {
+            ("", "")
+        } else {
+            (":NODES", ":NAME")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// DOT graph, to be rendered with `dot`
+    Dot,
+    /// Shell script sending the i3 commands recreating the layout
+    I3Commands,
+    /// Nested bullet lists, for documentation
+    Markdown,
+    /// SVG image laid out without Graphviz (requires the `svg` feature)
+    Svg,
 }
 
 #[derive(PartialEq, Eq)]
@@ -64,14 +292,13 @@ impl TryFrom<&Value> for BorderType {
     }
 }
 
-impl ToString for BorderType {
-    fn to_string(&self) -> String {
-        match &self {
+impl fmt::Display for BorderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match &self {
             Self::Pixel => "pixel",
             Self::None => "none",
             Self::Normal => "normal",
-        }
-        .into()
+        })
     }
 }
 
@@ -110,18 +337,19 @@ impl TryFrom<&Value> for FloatMode {
     }
 }
 
-impl ToString for FloatMode {
-    fn to_string(&self) -> String {
+impl FloatMode {
+    /// Name of the mode in i3's trees
+    const fn key(&self) -> &'static str {
         match self {
-            Self::AutoOff => "Auto Off",
-            Self::AutoOn => "Auto On",
-            Self::UserOn => "User On",
-            Self::UserOff => "User Off",
+            Self::AutoOff => "auto_off",
+            Self::AutoOn => "auto_on",
+            Self::UserOn => "user_on",
+            Self::UserOff => "user_off",
         }
-        .into()
     }
 }
 
+#[derive(PartialEq, Eq)]
 enum Layout {
     Tabbed,
     SplitV,
@@ -148,17 +376,30 @@ impl TryFrom<&Value> for Layout {
     }
 }
 
-impl ToString for Layout {
-    fn to_string(&self) -> String {
+impl Layout {
+    /// Symbol of the layout, for the nodes too small for words
+    const fn glyph(&self) -> &'static str {
         match self {
+            Self::Tabbed => "⊤",
+            Self::SplitV => "↕",
+            Self::SplitH => "↔",
+            Self::Stacked => "☰",
+            Self::Output => "▣",
+            Self::DockArea => "⚓",
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
             Self::Tabbed => "tabbed",
             Self::SplitV => "splitv",
             Self::SplitH => "splith",
             Self::Stacked => "stacked",
             Self::Output => "output",
             Self::DockArea => "dockarea",
-        }
-        .into()
+        })
     }
 }
 
@@ -172,17 +413,16 @@ enum TreeType {
     FloatingCon,
 }
 
-impl ToString for TreeType {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for TreeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
             Self::Root => "root",
             Self::Output => "output",
             Self::Workspace => "workspace",
             Self::DockArea => "dockarea",
             Self::Con => "con",
             Self::FloatingCon => "floating_con",
-        }
-        .into()
+        })
     }
 }
 
@@ -211,40 +451,73 @@ impl TryFrom<&Value> for TreeType {
     }
 }
 
+#[derive(PartialEq, Eq)]
+enum ScratchpadState {
+    None,
+    Fresh,
+    Changed,
+}
+
+impl TryFrom<&Value> for ScratchpadState {
+    type Error = String;
+
+    fn try_from(val: &Value) -> Result<Self, Self::Error> {
+        let st = utils::try_string(val)?;
+        match st {
+            "none" => Ok(Self::None),
+            "fresh" => Ok(Self::Fresh),
+            "changed" => Ok(Self::Changed),
+            _ => Err(format!("Unknown scratchpad state \"{st}\"")),
+        }
+    }
+}
+
+impl fmt::Display for ScratchpadState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Fresh => "fresh",
+            Self::Changed => "changed",
+        })
+    }
+}
+
+/// Size and position of a node
+///
+/// Windows partly off the screen, as sway allows, have negative coordinates.
 #[derive(Deserialize)]
 struct TreeGeometry {
     height: u64,
     width: u64,
-    x: u64,
-    y: u64,
+    x: i64,
+    y: i64,
 }
 
 impl TryFrom<&Value> for TreeGeometry {
     type Error = String;
 
     fn try_from(val: &Value) -> Result<Self, Self::Error> {
-        let mut fields = HashMap::new();
-        for field in &["height", "width", "x", "y"] {
-            let field_ser = val
-                .get(field)
-                .ok_or_else(|| format!("Missing field \"{field}\""))?;
-            let val = utils::try_u64(field_ser)?;
-            fields.insert(*field, val);
-        }
+        let field = |name: &str| {
+            val.get(name)
+                .ok_or_else(|| format!("Missing field \"{name}\""))
+        };
 
         Ok(Self {
-            height: fields["height"],
-            width: fields["width"],
-            x: fields["x"],
-            y: fields["y"],
+            height: utils::try_u64(field("height")?)?,
+            width: utils::try_u64(field("width")?)?,
+            x: utils::try_i64(field("x")?)?,
+            y: utils::try_i64(field("y")?)?,
         })
     }
 }
 
 impl TreeGeometry {
-    fn pretty_print(&self) -> String {
+    fn pretty_print(&self, lang: i18n::Lang) -> String {
         format!(
-            "Geometry | {{ {{ Width: {width} | Height: {height} }} | {{ X: {x} | Y: {y} }} }}",
+            "{geometry} | {{ {{ {width_label} {width} | {height_label} {height} }} | {{ X: {x} | Y: {y} }} }}",
+            geometry = lang.tr("geometry"),
+            width_label = lang.tr("width"),
+            height_label = lang.tr("height"),
             width = self.width,
             height = self.height,
             x = self.x,
@@ -253,7 +526,19 @@ impl TreeGeometry {
     }
 }
 
+#[derive(Deserialize)]
+struct WindowProperties {
+    class: Option<String>,
+    instance: Option<String>,
+    title: Option<String>,
+    window_role: Option<String>,
+}
+
+// Reasoning: These are the states i3 reports for each container
+#[allow(clippy::struct_excessive_bools)]
 struct Node {
+    // Layout files have no container ids
+    id: Option<u64>,
     border: BorderType,
     floating: FloatMode,
     layout: Option<Layout>,
@@ -262,11 +547,41 @@ struct Node {
     tree_type: TreeType,
     // Some nodes have a -1 border
     current_border_width: Option<i64>,
+    // Only workspaces have a number
+    num: Option<i64>,
     nodes: Vec<Self>,
+    floating_nodes: Vec<Self>,
     geometry: Option<TreeGeometry>,
+    rect: Option<TreeGeometry>,
+    scratchpad_state: ScratchpadState,
     name: Option<String>,
     swallows: HashMap<String, String>,
+    window_properties: Option<WindowProperties>,
+    window_type: Option<String>,
+    // X11 window id, only on actual windows
+    window: Option<u64>,
     focused: bool,
+    urgent: bool,
+    // Whether the node comes first in the focus stack of its parent
+    focus_head: bool,
+    // Layouts of the single-child containers folded into this node
+    folded: Vec<Layout>,
+    // Number of descendants removed from the view
+    hidden: usize,
+    // Whether the node matches the highlight criteria
+    highlighted: bool,
+    // Share of its workspace the node takes, when scaling
+    share: Option<f64>,
+}
+
+/// Which parts of a node end up in the output
+struct Selection<'a> {
+    /// Whether the node itself is shown
+    print_self: bool,
+    /// Whether the whole subtree below the node is shown
+    print_children: bool,
+    /// The children to visit
+    children: Vec<&'a Node>,
 }
 
 impl TryFrom<&Value> for Node {
@@ -324,13 +639,10 @@ impl TryFrom<&Value> for Node {
                 let layout = obj.get("layout").map(Layout::try_from).transpose()?;
 
                 // Name is optional
-                let name = if let Some(v) = obj.get("name") {
-                    // Some(utils::try_string(v)?.to_owned())
-                    // Some Strings are null :(
-                    Some(utils::try_string(v).unwrap_or("(null)").to_owned())
-                } else {
-                    None
-                };
+                // Some Strings are null :(
+                let name = obj
+                    .get("name")
+                    .map(|v| utils::try_string(v).unwrap_or("(null)").to_owned());
 
                 // Geometry is optional and exclusive with layout
                 let geometry = if let Some(v) = obj.get("geometry") {
@@ -343,20 +655,55 @@ impl TryFrom<&Value> for Node {
                 };
 
                 // Nodes is the children...
-                let nodes = if let Some(v) = obj.get("nodes") {
-                    let vec = utils::try_vec(v)?;
-                    if vec.iter().any(|item| !matches!(item, Value::Object(_))) {
-                        return Err(String::from("Nodes contains non-objects"));
+                let children = |key: &str| {
+                    if let Some(v) = obj.get(key) {
+                        let vec = utils::try_vec(v)?;
+                        if vec.iter().any(|item| !matches!(item, Value::Object(_))) {
+                            return Err(format!("{key} contains non-objects"));
+                        }
+
+                        // Build the children
+                        vec.iter()
+                            .map(Self::try_from)
+                            .collect::<Result<Vec<Self>, String>>()
+                    } else {
+                        Ok(Vec::new())
                     }
+                };
+                let mut nodes = children("nodes")?;
+                // ...and floating windows live on their own
+                let mut floating_nodes = children("floating_nodes")?;
 
-                    // Build the children
-                    vec.iter()
-                        .map(Self::try_from)
-                        .collect::<Result<Vec<Self>, String>>()?
+                // The focus stack starts with the child last focused
+                let focus_head = obj
+                    .get("focus")
+                    .map(utils::try_vec)
+                    .transpose()?
+                    .and_then(|focus| focus.first())
+                    .map(utils::try_u64)
+                    .transpose()?;
+                if let Some(head) = focus_head {
+                    for child in nodes.iter_mut().chain(&mut floating_nodes) {
+                        child.focus_head = child.id == Some(head);
+                    }
+                }
+
+                // Rect is only there in actual trees
+                let rect = if let Some(v) = obj.get("rect") {
+                    Some(
+                        serde_json::from_value::<TreeGeometry>(v.clone())
+                            .map_err(|e| format!("JSON Error: {e}"))?,
+                    )
                 } else {
-                    Vec::new()
+                    None
                 };
 
+                let scratchpad_state = obj
+                    .get("scratchpad_state")
+                    .map(ScratchpadState::try_from)
+                    .transpose()?
+                    .unwrap_or(ScratchpadState::None);
+
                 // Swallows is optional
                 // But when it's not there, do an empty map
                 let swallows = if let Some(v) = obj.get("swallows") {
@@ -394,18 +741,55 @@ impl TryFrom<&Value> for Node {
                     Ok(HashMap::new())
                 }?;
 
+                // Window properties only exist on actual windows
+                let window_properties = if let Some(v) = obj.get("window_properties") {
+                    Some(
+                        serde_json::from_value::<WindowProperties>(v.clone())
+                            .map_err(|e| format!("JSON Error: {e}"))?,
+                    )
+                } else {
+                    None
+                };
+
+                let window_type = obj
+                    .get("window_type")
+                    .filter(|v| !v.is_null())
+                    .map(|v| utils::try_string(v).map(str::to_owned))
+                    .transpose()?;
+
                 let current_border_width = obj
                     .get("current_border_width")
                     .map(utils::try_i64)
                     .transpose()?;
 
+                let num = obj
+                    .get("num")
+                    .filter(|v| !v.is_null())
+                    .map(utils::try_i64)
+                    .transpose()?;
+
                 let focused = obj
                     .get("focused")
                     .map(utils::try_bool)
                     .transpose()?
                     .map_or_else(|| false, |&v| v);
 
+                let urgent = obj
+                    .get("urgent")
+                    .map(utils::try_bool)
+                    .transpose()?
+                    .map_or_else(|| false, |&v| v);
+
+                let id = obj.get("id").map(utils::try_u64).transpose()?;
+
+                let window = obj
+                    .get("window")
+                    .filter(|v| !v.is_null())
+                    .map(utils::try_u64)
+                    .transpose()?;
+
                 Ok(Self {
+                    id,
                     border,
                     floating,
                     layout,
@@ -413,11 +797,24 @@ impl TryFrom<&Value> for Node {
                     percent,
                     tree_type,
                     current_border_width,
+                    num,
                     nodes,
+                    floating_nodes,
                     geometry,
+                    rect,
+                    scratchpad_state,
                     name,
                     swallows,
+                    window_properties,
+                    window_type,
+                    window,
                     focused,
+                    urgent,
+                    focus_head: false,
+                    folded: Vec::new(),
+                    hidden: 0,
+                    highlighted: false,
+                    share: None,
                 })
             }
             _ => Err(String::from("Incompatible JSON value type")),
@@ -426,141 +823,213 @@ impl TryFrom<&Value> for Node {
 }
 
 impl Node {
+    /// Tiling children, followed by the floating containers
+    fn children(&self) -> impl Iterator<Item = &Self> {
+        self.nodes.iter().chain(&self.floating_nodes)
+    }
+
+    fn has_highlight(&self) -> bool {
+        self.highlighted || self.children().any(Self::has_highlight)
+    }
+
     fn has_focus(&self) -> bool {
         if self.focused {
             true
         } else {
-            self.nodes.iter().any(Self::has_focus)
+            self.children().any(Self::has_focus)
         }
     }
 
-    fn pretty_print(&self, id: &str, settings: &Settings, print_children: bool) -> String {
-        // Ok, start formatting:
-        // +-------------------------------------------------------+
-        // | <NAME>Name of the Window (truncated of course)        |
-        // +-------------------------------------------------------+
-        // | Layout / Geometry    | Percent   |   Current Border   |
-        // |----------------------|           |       Width        |
-        // | Tree Type | Floating |---------+-----------+----------|
-        // |----------------------+  Nodes  | Swallows  |  Marks   |
-        // | Border Type          |         |           |          |
-        // +--------------------------------+----------------------+
-
-        // Will cut text after this number of characters
-        const CUT_AT: usize = 50;
-
-        let print_children = print_children || self.tree_type == settings.expand_from;
-        let print_self = print_children || settings.print_parents;
+    /// Number of descendants, including the ones already hidden
+    fn descendants(&self) -> usize {
+        self.hidden + self.children().map(|n| 1 + n.descendants()).sum::<usize>()
+    }
+
+    /// Mention of the hidden descendants, if any
+    fn pretty_hidden(&self, lang: i18n::Lang) -> String {
+        if self.hidden == 0 {
+            String::new()
+        } else {
+            format!(" (+{} {})", self.hidden, lang.tr("hidden"))
+        }
+    }
+
+    /// Whether the node is one of the outputs or workspaces i3 keeps for
+    /// itself, like `__i3` and `__i3_scratch`
+    fn is_internal(&self) -> bool {
+        self.name
+            .as_deref()
+            .is_some_and(|name| name.starts_with("__"))
+    }
+
+    /// Whether the node is a leaf still waiting to swallow a window
+    fn is_placeholder(&self) -> bool {
+        self.nodes.is_empty() && !self.swallows.is_empty()
+    }
+
+    /// Whether the node has no children, be they shown or hidden
+    const fn is_leaf(&self) -> bool {
+        self.nodes.is_empty() && self.floating_nodes.is_empty() && self.hidden == 0
+    }
+
+    const fn is_window(&self) -> bool {
+        self.window_properties.is_some()
+    }
+
+    /// Number of nodes of the subtree, including this one, matching `kind`
+    fn count(&self, kind: fn(&Self) -> bool) -> usize {
+        usize::from(kind(self)) + self.children().map(|n| n.count(kind)).sum::<usize>()
+    }
+
+    /// Class and instance of a window, or of the window a placeholder waits for
+    fn application(&self) -> (Option<String>, Option<String>) {
+        // Actual windows know their properties, placeholders only have
+        // the regular expressions of their swallows
+        self.window_properties.as_ref().map_or_else(
+            || {
+                (
+                    self.swallows.get("class").map(|v| unregex(v)),
+                    self.swallows.get("instance").map(|v| unregex(v)),
+                )
+            },
+            |props| (props.class.clone(), props.instance.clone()),
+        )
+    }
+
+    /// State of a window, as named by i3 for its colors
+    fn client_state(&self) -> Option<&'static str> {
+        if self.is_placeholder() {
+            Some("placeholder")
+        } else if !self.is_window() {
+            None
+        } else if self.urgent {
+            Some("urgent")
+        } else if self.focused {
+            Some("focused")
+        } else if self.focus_head {
+            Some("focused_inactive")
+        } else {
+            Some("unfocused")
+        }
+    }
+
+    const fn is_floating(&self) -> bool {
+        matches!(self.floating, FloatMode::AutoOn | FloatMode::UserOn)
+    }
+
+    /// Applies the expansion rules of the settings to this node
+    fn select(&self, settings: &Settings, print_children: bool) -> Selection<'_> {
+        let print_children = print_children || settings.is_anchor(self);
+        let print_self = print_children || settings.print_parents || settings.focus_path;
 
         // Children
-        let children: Vec<&Self> = if print_children {
-            self.nodes.iter().collect()
+        // Once pruned, everything left was explicitly selected
+        let children: Vec<&Self> = if print_children || settings.is_filtering() {
+            self.children().collect()
         } else {
-            self.nodes.iter().filter(|&n| n.has_focus()).collect()
+            self.children()
+                .filter(|&n| n.has_focus() || settings.leads_to_anchor(n))
+                .collect()
         };
 
+        Selection {
+            print_self,
+            print_children,
+            children,
+        }
+    }
+
+    /// Layouts of the containers folded into this node, outermost first
+    fn breadcrumb(&self) -> Option<String> {
+        if self.folded.is_empty() {
+            None
+        } else {
+            Some(
+                self.folded
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(" › "),
+            )
+        }
+    }
+
+    /// Shape of the node and the attributes sizing it, minimal nodes being
+    /// small ellipses for the leaves and circles for the containers
+    fn shape<'a>(&self, settings: &Settings, style: &'a theme::Style) -> (&'a str, &'static str) {
+        match (settings.density() == Density::Minimal, self.is_leaf()) {
+            (false, _) => (style.shape(), ""),
+            (true, true) => ("ellipse", " height=0.3 fontsize=10"),
+            (true, false) => ("circle", " width=0.4 fixedsize=true"),
+        }
+    }
+
+    /// Short name of the node in a path, falling back to its type and layout
+    fn path_name(&self) -> String {
+        // i3 names some containers "(null)"
+        self.name
+            .as_deref()
+            .filter(|&name| name != "(null)")
+            .map_or_else(
+                || {
+                    self.layout.as_ref().map_or_else(
+                        || self.tree_type.to_string(),
+                        |layout| format!("{} ({layout})", self.tree_type),
+                    )
+                },
+                text::sanitize,
+            )
+    }
+
+    /// Subgraph placing the children the way the split shows them on screen
+    fn pretty_arrangement(&self, id: &str, count: usize) -> String {
+        let children = (0..count).map(|pos| format!("node_{id}_{pos}"));
+        match self.layout {
+            Some(Layout::SplitH) => format!(
+                "\tsubgraph split_{id} {{\n\t\trank=same; {};\n\t}}\n",
+                children.collect::<Vec<_>>().join("; ")
+            ),
+            // Invisible edges push each child below the previous one
+            Some(Layout::SplitV) => format!(
+                "\tsubgraph split_{id} {{\n\t\t{} [style=invis]\n\t}}\n",
+                children.collect::<Vec<_>>().join(" -> ")
+            ),
+            _ => String::new(),
+        }
+    }
+
+    fn pretty_print(&self, id: &str, settings: &Settings, print_children: bool) -> String {
+        let Selection {
+            print_self,
+            print_children,
+            children,
+        } = self.select(settings, print_children);
+
         if print_self {
-            // Build the label
-            let default = "(no name)".to_owned();
-
-            let name = self.name.as_ref().unwrap_or(&default).chars();
-
-            let cut_name = if name.clone().count() > CUT_AT {
-                name.take(CUT_AT)
-                    .map(|c| match c {
-                        '\\' => "\\\\".to_owned(),
-                        '\"' => "\\\"".to_owned(),
-                        '|' => "\\|".to_owned(),
-                        '^' => "\\^".to_owned(),
-                        '/' => "\\/".to_owned(),
-                        '<' => "&lt;".to_owned(),
-                        '>' => "&gt;".to_owned(),
-                        e => e.to_string(),
-                    })
-                    .collect::<String>()
-                    + "..."
-            } else {
-                name.collect::<String>()
-            };
-            // .split_at(std::cmp::min(50, name.len()));
-
-            let label = if settings.silent {
-                format!("{{<NAME>{focus}{name}|{{ {{ {{ Tree Type:\\n{tree_type} | Floating:\\n{floating} }} | {lygeom} }}| {{ {{ Percent:\\n{percent:0.3}% {cbwidth} }} {sm} }} }} }}",
-            name = cut_name,
-            focus = if self.has_focus() {"🔴 "} else {""},
-            tree_type = self.tree_type.to_string(),
-            floating = self.floating.to_string(),
-            // Occupied Space
-            percent = self.percent * 100_f64,
-            // Geometry
-            lygeom = self.layout.as_ref()
-                .map_or_else(|| self.geometry.as_ref().unwrap().pretty_print(),
-                    |ly| format!("<NODES>Layout:\\n{}", ly.to_string())),
-            // Border Type & Width
-            cbwidth = self.current_border_width
-                .map_or_else(String::new, |w|
-                             if w > 0 || self.border == BorderType::Normal {
-                                 format!(" | Border:{t}{v}{u}",
-                                         t = if self.border == BorderType::Normal { "\\nTitle" } else { "" },
-                                         v = if w > 0 { "\\n".to_string() + &w.to_string() } else { String::new() },
-                                         u = if w > 0 { self.border.unit() } else { String::new() })
-                             } else {
-                                 String::new()
-                             }),
-            sm = if self.swallows.is_empty() || settings.no_swallows {
-                if self.marks.is_empty()  {
-                    String::new()
-                } else {
-                    format!("| {{ Marks:\\n{} }}",
-                        self.marks.iter()
-                            .map(|i| format!("- \\\"{i}\\\"\\l"))
-                            .collect::<String>())
-                }
-            } else if self.marks.is_empty() {
-                    "| {{ <SWALLOWS>Swallows }}".into()
-            } else {
-                format!("| {{ <SWALLOWS>Swallows | Marks:\\n{} }}",
-                    self.marks.iter()
-                        .map(|i| format!("- \\\"{i}\\\"\\l"))
-                        .collect::<String>())
-            }
-        )
-            } else {
-                format!("{{<NAME>{focus}{name}|{{ {{ {{ Tree Type:\\n{tree_type} | Floating:\\n{floating} }} | Border Type:\\n{border_type} | {lygeom} }}| {{ {{ Percent:\\n{percent:0.3}% | Border Width:\\n{cbwidth} }} | {{ {swallows} {marks} }} }} }} }}",
-            name = cut_name,
-            focus = if self.has_focus() {"🔴 "} else {""},
-            tree_type = self.tree_type.to_string(),
-            floating = self.floating.to_string(),
-            border_type = self.border.to_string(),
-            percent = self.percent * 100_f64,
-            lygeom = self.layout.as_ref()
-                .map_or_else(|| self.geometry.as_ref().unwrap().pretty_print(),
-                    |ly| format!("<NODES>Layout:\\n{}", ly.to_string())),
-            cbwidth = self.current_border_width
-                .map_or("N/A".into(), |e| format!("{e}")),
-            marks = if self.marks.is_empty() {
-                "No marks".into()
-            } else {
-                format!("Marks:\\n{}",
-                        self.marks.iter()
-                            .map(|i| format!("- \\\"{i}\\\"\\l"))
-                            .collect::<String>())
-            },
-            swallows = if self.swallows.is_empty() || settings.no_swallows {
-                ""
-            } else {
-                "<SWALLOWS>Swallows | "
-            }
-        )
-            };
-            let mut node_itself = format!("\tnode_{id} [shape=record label=\"{label}\"]\n");
-            if !(self.swallows.is_empty() || settings.no_swallows) {
+            let label = settings.label_template().render(self, settings);
+            let appearance = settings
+                .app_map
+                .as_ref()
+                .and_then(|apps| apps.appearance(self));
+            let style = settings
+                .theme
+                .node_style(self, appearance.as_ref().map(|a| a.color.as_str()));
+            let minimal = settings.density() == Density::Minimal;
+            let (shape, minimal_size) = self.shape(settings, &style);
+            let mut node_itself = format!(
+                "\tnode_{id} [shape={shape} label=\"{label}\"{attrs}{minimal_size}{size}]\n",
+                attrs = style.attributes(),
+                size = scale::attributes(self, settings.scale_target)
+            );
+            if !(self.swallows.is_empty() || settings.no_swallows || minimal) {
                 // Build the swallows
-                let the_swallows = format!("\tnode_{id}_swallows [shape=record label=\"{{ <HEAD>Swallows | {} }}\"]\n\tnode_{id}:SWALLOWS -> node_{id}_swallows:HEAD",
+                let the_swallows = format!("\tnode_{id}_swallows [shape=record label=\"{{ <HEAD>{} | {} }}\"]\n\tnode_{id}:SWALLOWS -> node_{id}_swallows:HEAD",
+                settings.lang.tr("swallows"),
                 self.swallows.iter()
-                    .map(|(key, val)| {
-                        format!("- {key}: \\\"{val}\\\"\\l")
-                    }).collect::<String>());
+                    .fold(String::new(), |mut acc, (key, val)| {
+                        let _ = write!(acc, "- {key}: \\\"{val}\\\"\\l");
+                        acc
+                    }));
                 node_itself.push_str(&the_swallows);
             }
 
@@ -569,7 +1038,26 @@ impl Node {
                 let child_id = format!("{id}_{pos}");
                 node_itself.push_str(&child.pretty_print(&child_id, settings, print_children));
 
-                node_itself.push_str(&format!("\tnode_{id}:NODES -> node_{child_id}:NAME\n"));
+                let attrs = settings.theme.edge_style(child).attributes();
+                let (from, to) = settings.edge_ports();
+                let _ = writeln!(
+                    node_itself,
+                    "\tnode_{id}{from} -> node_{child_id}{to}{style}",
+                    style = if attrs.is_empty() {
+                        attrs
+                    } else {
+                        format!(" [{}]", attrs.trim_start())
+                    }
+                );
+            }
+
+            // Floating containers come last, and sit outside of the split
+            let tiling = children
+                .iter()
+                .take_while(|c| c.tree_type != TreeType::FloatingCon)
+                .count();
+            if settings.arrange && tiling > 1 {
+                node_itself.push_str(&self.pretty_arrangement(id, tiling));
             }
 
             node_itself
@@ -585,7 +1073,44 @@ impl Node {
     }
 }
 
-fn read_input() -> Result<String, String> {
+/// Escapes the characters with a meaning in DOT record labels
+fn escape_label(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_owned(),
+            '\"' => "\\\"".to_owned(),
+            '|' => "\\|".to_owned(),
+            '{' => "\\{".to_owned(),
+            '}' => "\\}".to_owned(),
+            '^' => "\\^".to_owned(),
+            '/' => "\\/".to_owned(),
+            '<' => "&lt;".to_owned(),
+            '>' => "&gt;".to_owned(),
+            e => e.to_string(),
+        })
+        .collect()
+}
+
+/// Turns a simple swallow regular expression back into plain text
+fn unregex(st: &str) -> String {
+    st.trim_start_matches('^')
+        .trim_end_matches('$')
+        .replace('\\', "")
+}
+
+fn read_input(files: &[String]) -> Result<String, String> {
+    if !files.is_empty() {
+        // Each file holds whole documents
+        return files
+            .iter()
+            .map(|file| {
+                std::fs::read_to_string(file)
+                    .map(|code| code.trim().to_owned())
+                    .map_err(|e| format!("Cannot read \"{file}\": {e}"))
+            })
+            .collect::<Result<Vec<String>, String>>()
+            .map(|codes| codes.join("\n\n"));
+    }
     let mut buffer = Vec::new();
     std::io::stdin()
         .read_to_end(&mut buffer)
@@ -595,30 +1120,144 @@ fn read_input() -> Result<String, String> {
     Ok(decoded)
 }
 
+/// Parses the documents of the input, separated by blank lines
+fn parse_trees(code: &str) -> Result<Vec<Node>, String> {
+    code.trim()
+        .split("\n\n")
+        .map(|window| {
+            let mp: serde_json::Value =
+                serde_json::from_str(window).map_err(|e| format!("JSON parse error: \"{e}\""))?;
+            Node::try_from(&mp)
+        })
+        .collect()
+}
+
+fn render_dot(trees: &[Node], settings: &Settings, title: &str) -> String {
+    let mut graph = String::from("digraph tuilade {\n");
+    // Keep the children in their i3 order
+    graph.push_str("\tordering=out\n");
+    graph.push_str(&settings.theme.header());
+    if !settings.silent {
+        let _ = writeln!(graph, "\tnode_title[shape=rectangle label = \"{title}\"]");
+    }
+    if settings.legend {
+        graph.push_str(&legend::render(settings));
+    }
+    for (root_id, tree) in trees.iter().enumerate() {
+        let _ = writeln!(
+            graph,
+            "{}",
+            tree.pretty_print(&format!("{root_id}"), settings, settings.is_anchor(tree))
+        );
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+/// Prints a graph as DOT, or as SVG when asked to
+fn print_graph(dot: &str, format: &OutputFormat) -> Result<(), String> {
+    match format {
+        #[cfg(feature = "svg")]
+        OutputFormat::Svg => print!("{}", svg::render(dot)?),
+        #[cfg(not(feature = "svg"))]
+        OutputFormat::Svg => {
+            return Err(String::from(
+                "SVG output requires building tuilade with the \"svg\" feature",
+            ))
+        }
+        _ => print!("{dot}"),
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), String> {
-    let settings = Settings::parse();
-    let code = read_input()?;
+    let mut settings = Settings::parse();
+    if let Some(colors) = &settings.i3_config {
+        settings.theme.apply_i3_colors(colors);
+    }
+    // The command line wins over the theme, be it with a template or a
+    // density
+    if settings.label_template.is_none() && settings.density.is_none() {
+        settings.label_template = settings
+            .theme
+            .label_template
+            .as_deref()
+            .map(str::parse)
+            .transpose()?;
+    }
+    match &settings.command {
+        Some(Command::Diff {
+            old,
+            new,
+            changelog,
+        }) => return diff::run(old, new, *changelog, &settings),
+        Some(Command::Stats { files, json }) => return stats::run(files, *json, &settings),
+        Some(Command::Lint { files }) => return lint::run(files, &settings),
+        None => {}
+    }
+    let code = read_input(&settings.files)?;
     if code.is_empty() {
         return Ok(());
     }
 
-    println!("digraph tuilade {{");
-    if !settings.silent {
-        println!("\tnode_title[shape=rectangle label = \"Tuilade i3 viewer\"]");
+    let mut trees = parse_trees(&code)?;
+
+    // Hidden scratchpad windows live in the internal workspace, which the
+    // filters would mostly leave out
+    if settings.scratchpad {
+        match settings.format {
+            OutputFormat::I3Commands => {
+                return Err(String::from(
+                    "The scratchpad view cannot be written as i3 commands",
+                ))
+            }
+            OutputFormat::Markdown => {
+                print!("{}", scratchpad::render_markdown(&trees, &settings));
+            }
+            _ => print_graph(&scratchpad::render_dot(&trees, &settings), &settings.format)?,
+        }
+        return Ok(());
     }
-    for (root_id, window) in code.trim().split("\n\n").enumerate() {
-        let mp: serde_json::Value =
-            serde_json::from_str(window).map_err(|e| format!("JSON parse error: \"{e}\""))?;
-        let mp = Node::try_from(&mp)?;
-        println!(
-            "{}",
-            mp.pretty_print(
-                &format!("{root_id}"),
-                &settings,
-                mp.tree_type == settings.expand_from
-            )
+
+    let mut title = String::from(settings.lang.tr("viewer"));
+    // The header is only shown on graphs outside of silent mode, and asking
+    // the window manager for its version takes a moment
+    let graph = matches!(settings.format, OutputFormat::Dot | OutputFormat::Svg);
+    if graph && !settings.silent {
+        let metadata = metadata::Metadata::collect(&trees, &settings.files, settings.stamp);
+        for line in metadata.lines(settings.lang) {
+            let _ = write!(
+                title,
+                "\\n{}",
+                line.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+        }
+    }
+    if settings.only.is_some() {
+        // Counted before pruning, to tell how much is left out
+        let count = |kind: fn(&Node) -> bool| trees.iter().map(|t| t.count(kind)).sum::<usize>();
+        let _ = write!(
+            title,
+            "\\n{} {}, {} {}",
+            count(Node::is_placeholder),
+            settings.lang.tr("placeholders"),
+            count(Node::is_window),
+            settings.lang.tr("windows")
         );
     }
-    println!("}}");
+
+    // Measured on the whole tree, before pruning and folding
+    if let Some(scale) = settings.scale {
+        scale::measure(&mut trees, scale);
+    }
+    let trees = filter::apply(trees, &settings);
+
+    match settings.format {
+        OutputFormat::Dot | OutputFormat::Svg => {
+            print_graph(&render_dot(&trees, &settings, &title), &settings.format)?;
+        }
+        OutputFormat::I3Commands => print!("{}", i3cmd::generate(&trees)),
+        OutputFormat::Markdown => print!("{}", markdown::render(&trees, &settings)),
+    }
     Ok(())
 }
@@ -0,0 +1,163 @@
+//! Markdown outline of a tree, as nested bullet lists
+//!
+//! The nodes shown are the same as in the DOT graph, so the expansion
+//! settings apply here as well.
+
+use std::fmt::Write as _;
+
+use crate::{text, Node, Selection, Settings, TreeType};
+
+pub fn escape(st: &str) -> String {
+    st.chars().fold(String::new(), |mut acc, c| {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            acc.push('\\');
+        }
+        acc.push(c);
+        acc
+    })
+}
+
+/// Wraps a value in a code span, with a fence longer than its backticks
+fn code(value: &str) -> String {
+    let value = text::sanitize(value);
+    let longest = value
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest + 1);
+    // The spaces keep a backtick at either end out of the fence, and are
+    // stripped when rendered
+    let pad = if value.starts_with('`') || value.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{value}{pad}{fence}")
+}
+
+fn describe(node: &Node, settings: &Settings) -> String {
+    let mut line = format!(
+        "{focus}{glyph}**{name}** ({tree_type}",
+        focus = if node.has_focus() {
+            settings.theme.focus_marker.as_str()
+        } else {
+            ""
+        },
+        glyph = settings
+            .app_map
+            .as_ref()
+            .and_then(|apps| apps.appearance(node)?.glyph)
+            .map_or_else(String::new, |glyph| format!("{glyph} ")),
+        name = escape(&text::sanitize(
+            node.name
+                .as_deref()
+                .unwrap_or_else(|| settings.lang.tr("no_name"))
+        )),
+        tree_type = node.tree_type,
+    );
+    match (node.breadcrumb(), &node.layout) {
+        (Some(crumbs), Some(layout)) => {
+            let _ = write!(line, ", {crumbs} › {layout}");
+        }
+        (Some(crumbs), None) => {
+            let _ = write!(line, ", {crumbs}");
+        }
+        (None, Some(layout)) => {
+            let _ = write!(line, ", {layout}");
+        }
+        (None, None) => {}
+    }
+    let _ = write!(line, ", {:.1}%)", node.percent * 100_f64);
+    for mark in &node.marks {
+        let _ = write!(line, " {}", code(mark));
+    }
+    line.push_str(&node.pretty_hidden(settings.lang));
+    line
+}
+
+fn render_node(
+    node: &Node,
+    settings: &Settings,
+    print_children: bool,
+    depth: usize,
+    out: &mut String,
+) {
+    let Selection {
+        print_self,
+        print_children,
+        children,
+    } = node.select(settings, print_children);
+
+    if !print_self {
+        // Skipped parents do not add a level to the list
+        for child in children {
+            render_node(child, settings, print_children, depth, out);
+        }
+        return;
+    }
+
+    let indent = "  ".repeat(depth);
+    let details = settings.details && node.tree_type == TreeType::Workspace;
+    if details {
+        let _ = writeln!(out, "{indent}- <details>");
+        let _ = writeln!(
+            out,
+            "{indent}  <summary>{}</summary>\n",
+            describe(node, settings)
+        );
+    } else {
+        let _ = writeln!(out, "{indent}- {}", describe(node, settings));
+    }
+
+    if !settings.no_swallows {
+        let mut swallows: Vec<_> = node.swallows.iter().collect();
+        swallows.sort();
+        for (key, val) in swallows {
+            let _ = writeln!(
+                out,
+                "{indent}  - {} {key}: {}",
+                settings.lang.tr("swallows").to_lowercase(),
+                code(val)
+            );
+        }
+    }
+
+    let has_children = !children.is_empty();
+    for child in children {
+        render_node(child, settings, print_children, depth + 1, out);
+    }
+
+    if details {
+        if has_children {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "{indent}  </details>");
+    }
+}
+
+/// Renders the given trees as a Markdown outline
+pub fn render(trees: &[Node], settings: &Settings) -> String {
+    let mut out = String::new();
+    for tree in trees {
+        render_node(tree, settings, settings.is_anchor(tree), 0, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::code;
+
+    #[test]
+    fn code_spans() {
+        assert_eq!(code("web"), "`web`");
+        assert_eq!(code("a`b"), "``a`b``");
+        assert_eq!(code("a``b`c"), "```a``b`c```");
+        assert_eq!(code("`web`"), "`` `web` ``");
+        assert_eq!(code("mark\nline"), "`markline`");
+    }
+}
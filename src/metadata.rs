@@ -0,0 +1,151 @@
+//! Header of the graph, telling where and when the trees were taken
+//!
+//! The host, the date and the version describe the machine running tuilade,
+//! so they are only added when asked, for trees taken live. The version is
+//! asked to the running window manager, sway when its socket is set and i3
+//! otherwise; it is left out when neither answers.
+
+use serde_json::Value;
+use std::{process::Command, time::SystemTime};
+
+use crate::{i18n::Lang, Node, TreeType};
+
+/// Number of windows in the workspaces of a node, leaving out the dock
+/// clients
+fn windows(node: &Node, in_workspace: bool) -> usize {
+    let in_workspace = in_workspace || node.tree_type == TreeType::Workspace;
+    usize::from(in_workspace && node.is_window())
+        + node
+            .children()
+            .map(|child| windows(child, in_workspace))
+            .sum::<usize>()
+}
+
+/// What is known of the trees and of the machine they come from
+pub struct Metadata {
+    host: Option<String>,
+    date: Option<String>,
+    version: Option<String>,
+    sources: Vec<String>,
+    outputs: usize,
+    workspaces: usize,
+    windows: usize,
+}
+
+/// Version of the running window manager, as it reports it
+fn version() -> Option<String> {
+    let msg = if std::env::var_os("SWAYSOCK").is_some() {
+        "swaymsg"
+    } else {
+        "i3-msg"
+    };
+    let output = Command::new(msg)
+        .args(["-t", "get_version"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let reply: Value = serde_json::from_slice(&output.stdout).ok()?;
+    reply
+        .get("human_readable")
+        .and_then(Value::as_str)
+        .map(|version| format!("{msg}: {version}"))
+}
+
+/// Name of the machine, as the kernel knows it
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|name| name.trim().to_owned())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| String::from("?"))
+}
+
+/// Current date and time, in UTC
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from the days since the epoch, counting the years from
+    // March so that leap days come last
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+impl Metadata {
+    /// Gathers the metadata of trees read from `sources`, an empty list
+    /// standing for the standard input, stamped with the running machine if
+    /// `stamp` is set
+    pub fn collect(trees: &[Node], sources: &[String], stamp: bool) -> Self {
+        let count = |kind: fn(&Node) -> bool| trees.iter().map(|t| t.count(kind)).sum::<usize>();
+        Self {
+            host: stamp.then(hostname),
+            date: stamp.then(now),
+            version: stamp.then(version).flatten(),
+            sources: sources.to_vec(),
+            outputs: count(|n| n.tree_type == TreeType::Output && !n.is_internal()),
+            workspaces: count(|n| n.tree_type == TreeType::Workspace && !n.is_internal()),
+            // Layout files start below a workspace
+            windows: trees
+                .iter()
+                .map(|t| {
+                    windows(
+                        t,
+                        matches!(t.tree_type, TreeType::Con | TreeType::FloatingCon),
+                    )
+                })
+                .sum(),
+        }
+    }
+
+    /// Lines of the header, without the title
+    pub fn lines(&self, lang: Lang) -> Vec<String> {
+        let sources = if self.sources.is_empty() {
+            String::from(lang.tr("stdin"))
+        } else {
+            self.sources.join(", ")
+        };
+        let mut lines = Vec::new();
+        if let Some(host) = &self.host {
+            lines.push(format!("{} {host}", lang.tr("host")));
+        }
+        if let Some(date) = &self.date {
+            lines.push(format!("{} {date}", lang.tr("date")));
+        }
+        if let Some(version) = &self.version {
+            lines.push(format!("{} {version}", lang.tr("version")));
+        }
+        lines.push(format!("{} {sources}", lang.tr("sources")));
+        // Counted before filtering, so that they tell what was left out
+        lines.push(format!(
+            "{} {} {}, {} {}, {} {}",
+            lang.tr("whole_tree"),
+            self.outputs,
+            lang.tr("outputs"),
+            self.workspaces,
+            lang.tr("workspaces"),
+            self.windows,
+            lang.tr("windows")
+        ));
+        lines
+    }
+}
@@ -0,0 +1,83 @@
+//! Sizing of the nodes after the room their container takes on screen
+//!
+//! The share of a container is measured against its workspace, either from
+//! the `percent` of every split down to it, or from the area of its `rect`.
+//! Nodes above the workspaces are not scaled.
+
+use crate::{Node, TreeType};
+
+/// How the room taken by a container is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Scale {
+    /// The percents of the splits down from the workspace
+    Percent,
+    /// The area of the container, relative to its workspace
+    Area,
+}
+
+/// What grows with the room taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScaleTarget {
+    /// The minimal width and height of the node
+    Size,
+    /// The font of the node
+    Font,
+}
+
+fn area(node: &Node) -> Option<f64> {
+    // Reasoning: Screens are far from 2^52 pixels wide
+    #[allow(clippy::cast_precision_loss)]
+    node.rect
+        .as_ref()
+        .map(|r| (r.width * r.height) as f64)
+        .filter(|&area| area > 0_f64)
+}
+
+/// Sets the share of the node, knowing that of its parent and the area of
+/// its workspace
+fn measure_node(node: &mut Node, scale: Scale, parent: Option<f64>, workspace: Option<f64>) {
+    let workspace = if node.tree_type == TreeType::Workspace {
+        area(node)
+    } else {
+        workspace
+    };
+    node.share = match (&node.tree_type, scale) {
+        (TreeType::Workspace, _) => Some(1_f64),
+        // Floating containers cover part of their workspace without splitting
+        // it, so they have no percent
+        (TreeType::FloatingCon, _) | (_, Scale::Area) => {
+            workspace.zip(area(node)).map(|(w, a)| a / w)
+        }
+        (_, Scale::Percent) => parent.map(|p| p * node.percent),
+    }
+    .map(|share| share.clamp(0_f64, 1_f64));
+
+    let share = node.share;
+    for child in node.nodes.iter_mut().chain(&mut node.floating_nodes) {
+        measure_node(child, scale, share, workspace);
+    }
+}
+
+/// Measures the share of their workspace every container takes
+pub fn measure(trees: &mut [Node], scale: Scale) {
+    for tree in trees {
+        // Layout files start below a workspace, which they fill
+        let parent = (tree.tree_type == TreeType::Con).then_some(1_f64);
+        measure_node(tree, scale, parent, None);
+    }
+}
+
+/// Attributes sizing a node, each preceded by a space
+pub fn attributes(node: &Node, target: ScaleTarget) -> String {
+    // The square root keeps the area of the node in proportion
+    node.share
+        .map(f64::sqrt)
+        .map_or_else(String::new, |factor| match target {
+            ScaleTarget::Size => format!(
+                " width={:.2} height={:.2}",
+                9_f64.mul_add(factor, 1_f64),
+                4.5_f64.mul_add(factor, 0.5_f64)
+            ),
+            ScaleTarget::Font => format!(" fontsize={:.1}", 18_f64.mul_add(factor, 6_f64)),
+        })
+}
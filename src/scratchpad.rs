@@ -0,0 +1,188 @@
+//! Listing of the scratchpad windows
+//!
+//! Scratchpad windows are floating containers with a scratchpad state. The
+//! hidden ones live on the `__i3_scratch` workspace of the `__i3` output,
+//! while the ones currently shown sit with the other floating windows of a
+//! workspace.
+
+use std::fmt::Write as _;
+
+use crate::{
+    escape_label, i18n::Lang, markdown, text, Node, ScratchpadState, Settings, TreeGeometry,
+    TreeType,
+};
+
+/// Name of the workspace holding the hidden scratchpad windows
+const SCRATCH_WORKSPACE: &str = "__i3_scratch";
+
+enum Location<'a> {
+    /// Currently shown on a workspace
+    Shown(&'a str),
+    /// Hidden, with the output it was last shown on if its position tells
+    Hidden(Option<&'a str>),
+}
+
+impl Location<'_> {
+    fn describe(&self, lang: Lang) -> String {
+        match self {
+            Self::Shown(workspace) => format!("{} {workspace}", lang.tr("shown_on")),
+            Self::Hidden(Some(output)) => format!(
+                "{}, {} {output}",
+                lang.tr("hidden_window"),
+                lang.tr("last_shown_on")
+            ),
+            Self::Hidden(None) => String::from(lang.tr("hidden_window")),
+        }
+    }
+}
+
+struct Entry<'a> {
+    /// The floating container
+    container: &'a Node,
+    location: Location<'a>,
+}
+
+impl Entry<'_> {
+    /// Name of the window held by the container
+    fn name(&self, lang: Lang) -> &str {
+        let mut node = self.container;
+        while let Some(child) = node.nodes.first() {
+            node = child;
+        }
+        node.name
+            .as_deref()
+            .or(self.container.name.as_deref())
+            .unwrap_or_else(|| lang.tr("no_name"))
+    }
+}
+
+const fn contains(rect: &TreeGeometry, x: i64, y: i64) -> bool {
+    x >= rect.x
+        && x < rect.x.saturating_add_unsigned(rect.width)
+        && y >= rect.y
+        && y < rect.y.saturating_add_unsigned(rect.height)
+}
+
+fn outputs<'a>(node: &'a Node, found: &mut Vec<&'a Node>) {
+    if node.tree_type == TreeType::Output {
+        found.push(node);
+    } else {
+        for child in &node.nodes {
+            outputs(child, found);
+        }
+    }
+}
+
+fn collect<'a>(
+    node: &'a Node,
+    workspace: Option<&'a str>,
+    outputs: &[&'a Node],
+    entries: &mut Vec<Entry<'a>>,
+) {
+    let workspace = if node.tree_type == TreeType::Workspace {
+        node.name.as_deref()
+    } else {
+        workspace
+    };
+
+    for floating in &node.floating_nodes {
+        if floating.scratchpad_state == ScratchpadState::None {
+            continue;
+        }
+        let location = match workspace {
+            Some(SCRATCH_WORKSPACE) | None => {
+                // Hidden windows keep the position they were last shown at
+                let output = floating.rect.as_ref().and_then(|rect| {
+                    let (x, y) = (
+                        rect.x.saturating_add_unsigned(rect.width / 2),
+                        rect.y.saturating_add_unsigned(rect.height / 2),
+                    );
+                    outputs
+                        .iter()
+                        .find(|o| o.rect.as_ref().is_some_and(|r| contains(r, x, y)))
+                        .and_then(|o| o.name.as_deref())
+                });
+                Location::Hidden(output)
+            }
+            Some(workspace) => Location::Shown(workspace),
+        };
+        entries.push(Entry {
+            container: floating,
+            location,
+        });
+    }
+
+    for child in &node.nodes {
+        collect(child, workspace, outputs, entries);
+    }
+}
+
+fn entries(trees: &[Node]) -> Vec<Entry<'_>> {
+    let mut found = Vec::new();
+    for tree in trees {
+        outputs(tree, &mut found);
+    }
+    // The internal output covers no actual screen
+    found.retain(|o| o.name.as_deref() != Some("__i3"));
+
+    let mut entries = Vec::new();
+    for tree in trees {
+        collect(tree, None, &found, &mut entries);
+    }
+    entries
+}
+
+/// Renders the scratchpad windows as a DOT graph
+pub fn render_dot(trees: &[Node], settings: &Settings) -> String {
+    let mut graph = String::from("digraph tuilade {\n");
+    graph.push_str(&settings.theme.header());
+    if !settings.silent {
+        let _ = writeln!(
+            graph,
+            "\tnode_title[shape=rectangle label = \"{}\"]",
+            settings.lang.tr("scratchpad")
+        );
+    }
+    for (pos, entry) in entries(trees).iter().enumerate() {
+        let _ = writeln!(
+            graph,
+            "\tscratchpad_{pos} [shape=record label=\"{{<NAME>{name}|{{ {state_label}\\n{state} | {location} }}{geometry}}}\"]",
+            name = escape_label(&text::sanitize(entry.name(settings.lang))),
+            state_label = settings.lang.tr("state"),
+            state = entry.container.scratchpad_state,
+            location = escape_label(&entry.location.describe(settings.lang)),
+            geometry = entry
+                .container
+                .rect
+                .as_ref()
+                .map_or_else(String::new, |r| format!("|{{ {} }}", r.pretty_print(settings.lang))),
+        );
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+/// Renders the scratchpad windows as a Markdown list
+pub fn render_markdown(trees: &[Node], settings: &Settings) -> String {
+    entries(trees).iter().fold(String::new(), |mut out, entry| {
+        let _ = write!(
+            out,
+            "- **{name}** ({state}, {location})",
+            name = markdown::escape(&text::sanitize(entry.name(settings.lang))),
+            state = entry.container.scratchpad_state,
+            location = entry.location.describe(settings.lang),
+        );
+        if let Some(rect) = &entry.container.rect {
+            let _ = write!(
+                out,
+                " {width}×{height} at {x},{y}",
+                width = rect.width,
+                height = rect.height,
+                x = rect.x,
+                y = rect.y
+            );
+        }
+        out.push('\n');
+        out
+    })
+}
@@ -0,0 +1,427 @@
+//! Statistics of the trees, as tables or JSON
+//!
+//! Only the content of the workspaces counts, not the dock clients nor the
+//! containers holding the workspaces. The windows inside floating containers
+//! count as floating. The outputs and workspaces i3 keeps for itself are left
+//! out of the tables, as in the header of the graph, but their windows, like
+//! the hidden scratchpad ones, still count.
+
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fmt::Write as _};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{i18n::Lang, parse_trees, read_input, Node, Settings, TreeType};
+
+/// Widest bar of the histograms, in columns
+const BAR_WIDTH: usize = 30;
+
+#[derive(Default)]
+pub struct Stats {
+    /// Workspaces with their output and their number of windows
+    workspaces: Vec<(String, String, usize)>,
+    /// Outputs with their number of windows
+    outputs: Vec<(String, usize)>,
+    /// Containers holding children, by layout
+    layouts: BTreeMap<String, usize>,
+    /// Windows and placeholders, by depth
+    depths: BTreeMap<usize, usize>,
+    floating: usize,
+    tiling: usize,
+    /// Paths of the marked containers, with their marks
+    marked: Vec<(String, Vec<String>)>,
+    placeholders: usize,
+    /// Depth and path of the deepest node
+    deepest: (usize, String),
+}
+
+/// Where a node sits, as known from its ancestors
+#[derive(Clone, Copy)]
+struct Place<'a> {
+    path: &'a str,
+    depth: usize,
+    output: Option<usize>,
+    workspace: Option<usize>,
+    floating: bool,
+    /// Inside a workspace, or in a layout file
+    content: bool,
+    /// Inside an output or workspace i3 keeps for itself
+    internal: bool,
+}
+
+impl Stats {
+    /// Gathers the statistics of the trees
+    pub fn collect(trees: &[Node]) -> Self {
+        let mut stats = Self::default();
+        for tree in trees {
+            stats.visit(
+                tree,
+                Place {
+                    path: "",
+                    depth: 0,
+                    output: None,
+                    workspace: None,
+                    floating: false,
+                    // Layout files start below a workspace
+                    content: matches!(tree.tree_type, TreeType::Con | TreeType::FloatingCon),
+                    internal: false,
+                },
+            );
+        }
+        stats
+    }
+
+    fn visit(&mut self, node: &Node, place: Place) {
+        let path = if place.path.is_empty() {
+            node.path_name()
+        } else {
+            format!("{} › {}", place.path, node.path_name())
+        };
+        let mut place = Place {
+            path: &path,
+            floating: place.floating || node.tree_type == TreeType::FloatingCon,
+            content: place.content || node.tree_type == TreeType::Workspace,
+            internal: place.internal || node.is_internal(),
+            ..place
+        };
+        match node.tree_type {
+            TreeType::Output if !node.is_internal() => {
+                place.output = Some(self.outputs.len());
+                self.outputs.push((node.path_name(), 0));
+            }
+            TreeType::Workspace if !node.is_internal() => {
+                let output = place
+                    .output
+                    .map_or_else(String::new, |o| self.outputs[o].0.clone());
+                place.workspace = Some(self.workspaces.len());
+                self.workspaces.push((node.path_name(), output, 0));
+            }
+            _ => {}
+        }
+
+        let content = place.content && node.tree_type != TreeType::Workspace;
+        if content && node.is_window() {
+            if let Some(o) = place.output {
+                self.outputs[o].1 += 1;
+            }
+            if let Some(w) = place.workspace {
+                self.workspaces[w].2 += 1;
+            }
+            if place.floating {
+                self.floating += 1;
+            } else {
+                self.tiling += 1;
+            }
+        }
+        if node.is_placeholder() {
+            self.placeholders += 1;
+        }
+        if content && (node.is_window() || node.is_placeholder()) {
+            *self.depths.entry(place.depth).or_default() += 1;
+        }
+        if let Some(layout) = node
+            .layout
+            .as_ref()
+            .filter(|_| place.content && !place.internal && !node.nodes.is_empty())
+        {
+            *self.layouts.entry(layout.to_string()).or_default() += 1;
+        }
+        if !node.marks.is_empty() {
+            self.marked.push((path.clone(), node.marks.clone()));
+        }
+        if place.depth > self.deepest.0 || self.deepest.1.is_empty() {
+            self.deepest = (place.depth, path.clone());
+        }
+
+        for child in node.nodes.iter().chain(&node.floating_nodes) {
+            self.visit(
+                child,
+                Place {
+                    depth: place.depth + 1,
+                    ..place
+                },
+            );
+        }
+    }
+
+    /// The statistics as a JSON object
+    pub fn to_json(&self) -> Value {
+        json!({
+            "workspaces": self.workspaces.iter().map(|(name, output, windows)| json!({
+                "name": name,
+                "output": output,
+                "windows": windows,
+            })).collect::<Vec<_>>(),
+            "outputs": self.outputs.iter().map(|(name, windows)| json!({
+                "name": name,
+                "windows": windows,
+            })).collect::<Vec<_>>(),
+            "layouts": self.layouts,
+            "depths": self.depths.iter().map(|(depth, count)| json!({
+                "depth": depth,
+                "count": count,
+            })).collect::<Vec<_>>(),
+            "floating": self.floating,
+            "tiling": self.tiling,
+            "marked": self.marked.iter().map(|(path, marks)| json!({
+                "path": path,
+                "marks": marks,
+            })).collect::<Vec<_>>(),
+            "placeholders": self.placeholders,
+            "deepest": {
+                "depth": self.deepest.0,
+                "path": self.deepest.1,
+            },
+        })
+    }
+
+    /// The statistics as text tables
+    pub fn to_text(&self, lang: Lang) -> String {
+        let mut out = String::new();
+        let mut section = |title: &str, headers: &[&str], rows: Vec<Vec<String>>| {
+            if !rows.is_empty() {
+                let _ = writeln!(out, "{title}\n\n{}", table(headers, &rows));
+            }
+        };
+        section(
+            lang.tr("stats_workspaces"),
+            &[
+                lang.tr("stats_workspace"),
+                lang.tr("stats_output"),
+                lang.tr("stats_windows"),
+            ],
+            self.workspaces
+                .iter()
+                .map(|(name, output, windows)| {
+                    vec![name.clone(), output.clone(), windows.to_string()]
+                })
+                .collect(),
+        );
+        section(
+            lang.tr("stats_outputs"),
+            &[lang.tr("stats_output"), lang.tr("stats_windows")],
+            self.outputs
+                .iter()
+                .map(|(name, windows)| vec![name.clone(), windows.to_string()])
+                .collect(),
+        );
+        section(
+            lang.tr("stats_layouts"),
+            &[lang.tr("stats_layout"), lang.tr("stats_count"), ""],
+            histogram(
+                self.layouts
+                    .iter()
+                    .map(|(layout, &count)| (layout.clone(), count)),
+            ),
+        );
+        section(
+            lang.tr("stats_depths"),
+            &[lang.tr("stats_depth"), lang.tr("stats_count"), ""],
+            histogram(
+                self.depths
+                    .iter()
+                    .map(|(depth, &count)| (depth.to_string(), count)),
+            ),
+        );
+        section(
+            lang.tr("stats_marked"),
+            &[lang.tr("stats_path"), lang.tr("stats_marks")],
+            self.marked
+                .iter()
+                .map(|(path, marks)| vec![path.clone(), marks.join(", ")])
+                .collect(),
+        );
+        section(
+            lang.tr("stats_summary"),
+            &["", ""],
+            vec![
+                vec![lang.tr("stats_tiling").to_owned(), self.tiling.to_string()],
+                vec![
+                    lang.tr("stats_floating").to_owned(),
+                    self.floating.to_string(),
+                ],
+                vec![
+                    lang.tr("stats_placeholders").to_owned(),
+                    self.placeholders.to_string(),
+                ],
+                vec![
+                    lang.tr("stats_deepest").to_owned(),
+                    format!("{} ({})", self.deepest.1, self.deepest.0),
+                ],
+            ],
+        );
+        out
+    }
+}
+
+/// Rows of a histogram, each with a bar in proportion to its count
+fn histogram(counts: impl Iterator<Item = (String, usize)>) -> Vec<Vec<String>> {
+    let counts: Vec<(String, usize)> = counts.collect();
+    let max = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    counts
+        .into_iter()
+        .map(|(key, count)| {
+            let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max.max(1)));
+            vec![key, count.to_string(), bar]
+        })
+        .collect()
+}
+
+/// Lays out rows under their headers, in aligned columns
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].width())
+                .chain([headers[col].width()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.width())))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+    let mut table = String::new();
+    if headers.iter().any(|h| !h.is_empty()) {
+        let _ = writeln!(table, "{}", line(headers.to_vec()));
+        let rules: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+        let _ = writeln!(
+            table,
+            "{}",
+            line(rules.iter().map(String::as_str).collect())
+        );
+    }
+    for row in rows {
+        let _ = writeln!(table, "{}", line(row.iter().map(String::as_str).collect()));
+    }
+    table
+}
+
+/// Prints the statistics of the trees read from `files`, or from the
+/// standard input
+pub fn run(files: &[String], json: bool, settings: &Settings) -> Result<(), String> {
+    let code = read_input(files)?;
+    if code.is_empty() {
+        return Ok(());
+    }
+    let stats = Stats::collect(&parse_trees(&code)?);
+    if json {
+        println!("{:#}", stats.to_json());
+    } else {
+        print!("{}", stats.to_text(settings.lang));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Stats;
+    use crate::parse_trees;
+
+    fn collect(tree: &str) -> Stats {
+        Stats::collect(&parse_trees(tree).expect("the tree should parse"))
+    }
+
+    fn full_tree() -> Stats {
+        collect(include_str!("../tests/golden/i3cmd/full_tree.json"))
+    }
+
+    #[test]
+    fn windows_per_workspace_and_output() {
+        let stats = full_tree();
+        let workspaces: Vec<(&str, &str, usize)> = stats
+            .workspaces
+            .iter()
+            .map(|(name, output, windows)| (name.as_str(), output.as_str(), *windows))
+            .collect();
+        assert_eq!(
+            workspaces,
+            [
+                ("1", "eDP-1", 3),
+                ("2: chat", "eDP-1", 2),
+                ("3", "HDMI-1", 0)
+            ]
+        );
+        // Neither the dock clients nor the internal output count
+        let outputs: Vec<(&str, usize)> = stats
+            .outputs
+            .iter()
+            .map(|(name, windows)| (name.as_str(), *windows))
+            .collect();
+        assert_eq!(outputs, [("eDP-1", 5), ("HDMI-1", 0)]);
+    }
+
+    #[test]
+    fn tiling_and_floating() {
+        let stats = full_tree();
+        assert_eq!(stats.tiling, 5);
+        // The hidden scratchpad window
+        assert_eq!(stats.floating, 1);
+        assert_eq!(stats.placeholders, 0);
+    }
+
+    #[test]
+    fn layouts_of_the_workspace_content() {
+        let stats = full_tree();
+        let layouts: Vec<(&str, usize)> = stats
+            .layouts
+            .iter()
+            .map(|(layout, count)| (layout.as_str(), *count))
+            .collect();
+        assert_eq!(layouts, [("splith", 2), ("splitv", 2), ("tabbed", 1)]);
+    }
+
+    #[test]
+    fn layout_file() {
+        let stats = collect(include_str!("../tests/golden/i3cmd/layout_swallows.json"));
+        assert_eq!(stats.placeholders, 3);
+        assert_eq!(stats.tiling, 0);
+        assert!(stats.workspaces.is_empty());
+        assert_eq!(stats.layouts.get("splitv"), Some(&1));
+        assert_eq!(
+            stats.marked,
+            [(String::from("con (splitv)"), vec![String::from("left")])]
+        );
+    }
+
+    #[test]
+    fn json_depths_are_sorted_numerically() {
+        let mut stats = full_tree();
+        stats.depths.insert(10, 1);
+        let json = stats.to_json();
+        assert_eq!(
+            json["depths"],
+            json!([
+                {"depth": 4, "count": 2},
+                {"depth": 5, "count": 3},
+                {"depth": 6, "count": 1},
+                {"depth": 10, "count": 1},
+            ])
+        );
+    }
+
+    #[test]
+    fn json_object() {
+        let json = full_tree().to_json();
+        assert_eq!(json["tiling"], 5);
+        assert_eq!(json["floating"], 1);
+        assert_eq!(
+            json["outputs"],
+            json!([{"name": "eDP-1", "windows": 5}, {"name": "HDMI-1", "windows": 0}])
+        );
+        assert_eq!(
+            json["workspaces"][1],
+            json!({"name": "2: chat", "output": "eDP-1", "windows": 2})
+        );
+        assert_eq!(json["marked"][1]["marks"], json!(["chat"]));
+        assert_eq!(json["deepest"]["depth"], 6);
+    }
+}
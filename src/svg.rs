@@ -0,0 +1,19 @@
+//! In-process layout of the DOT graph, producing SVG without Graphviz
+
+use layout::{backends::svg::SVGWriter, gv};
+
+/// Lays out a DOT graph and renders it as an SVG document
+pub fn render(dot: &str) -> Result<String, String> {
+    let mut parser = gv::DotParser::new(dot);
+    let graph = parser
+        .process()
+        .map_err(|e| format!("DOT parse error: \"{e}\""))?;
+
+    let mut builder = gv::GraphBuilder::new();
+    builder.visit_graph(&graph);
+    let mut visual = builder.get();
+
+    let mut writer = SVGWriter::new();
+    visual.do_it(false, false, false, &mut writer);
+    Ok(writer.finalize())
+}
@@ -0,0 +1,563 @@
+//! Label templates, choosing what the records of the DOT graph show
+//!
+//! A template is a record label, where `{field}` is replaced by a value of
+//! the node. Values go through filters, like `{name|trunc:40}`, and numbers
+//! take a precision, like `{percent:%.1}`. Sections are only kept when a
+//! field has a value (`{?marks}...{/marks}`) or has none
+//! (`{!marks}...{/marks}`), and `{@key}` is a message of the catalogue, in
+//! the language of the labels. A brace followed by anything else than a
+//! letter, `?`, `!`, `/` or `@` is kept as is, to group the fields of the
+//! record.
+
+use std::{fmt::Write as _, iter::Peekable, str::Chars, str::FromStr, sync::LazyLock};
+
+use crate::{escape_label, i18n, text, BorderType, Node, Settings};
+
+/// Label of the nodes at the normal density
+const NORMAL: &str = r"{<NAME>{focus}{?glyph}{glyph} {/glyph}{name|trunc}{hidden}|{ { { {@tree_type}\n{tree_type} | {@floating}\n{floating} } | {@border_type}\n{border} | {?layout}<NODES>{@layout}\n{?folded}{folded} › {/folded}{layout}{/layout}{!layout}{@geometry} | { { {@width} {width} | {@height} {height} } | { X: {x} | Y: {y} } }{?folded} | {@folded}\n{folded}{/folded}{/layout} }| { { {@percent}\n{percent:%.3}% | {@border_width}\n{border_width}{!border_width}{@not_available}{/border_width} } | { {?swallows}<SWALLOWS>{@swallows} | {/swallows} {?marks}{@marks}\n{marks}{/marks}{!marks}{@no_marks}{/marks} } } } }";
+
+/// Rows added at the full density: identifiers, rect, urgency and window
+/// properties
+const FULL_ROWS: &str = r"| { { {@con_id}\n{id}{!id}{@not_available}{/id} | {@window}\n{window}{!window}{@not_available}{/window}{?num} | {@number}\n{num}{/num} } | { {@rect}\n{rect}{!rect}{@not_available}{/rect} | {@urgent}\n{?urgent}{@yes}{/urgent}{!urgent}{@no}{/urgent} } }{?window} | { {@class}\n{class} | {@instance}\n{instance} | {@window_title}\n{title|trunc}{?window_role} | {@window_role}\n{window_role}{/window_role}{?window_type} | {@window_type}\n{window_type}{/window_type} }{/window} }";
+
+fn parse_builtin(source: &str) -> Template {
+    source
+        .parse()
+        .unwrap_or_else(|e| unreachable!("Invalid built-in template: {e}"))
+}
+
+/// Leaves as their name, containers as the glyph of their layout
+pub static MINIMAL: LazyLock<Template> = LazyLock::new(|| {
+    parse_builtin(
+        "{?leaf}{focus}{?glyph}{glyph} {/glyph}{name|trunc}{/leaf}{!leaf}{layout_glyph}{/leaf}",
+    )
+});
+
+/// Leaves out empty or default fields, as in silent mode
+pub static COMPACT: LazyLock<Template> = LazyLock::new(|| {
+    parse_builtin(
+        r"{<NAME>{focus}{?glyph}{glyph} {/glyph}{name|trunc}{hidden}|{ { { {@tree_type}\n{tree_type} | {@floating}\n{floating} } | {?layout}<NODES>{@layout}\n{?folded}{folded} › {/folded}{layout}{/layout}{!layout}{@geometry} | { { {@width} {width} | {@height} {height} } | { X: {x} | Y: {y} } }{?folded} | {@folded}\n{folded}{/folded}{/layout} }| { { {@percent}\n{percent:%.3}% {?title_bar} | {@border}\n{@title}{?border_size}\n{border_size}{/border_size}{/title_bar}{!title_bar}{?border_size} | {@border}\n{border_size}{/border_size}{/title_bar} } {?swallows}| { <SWALLOWS>{@swallows}{?marks} | {@marks}\n{marks}{/marks} }{/swallows}{!swallows}{?marks}| { {@marks}\n{marks} }{/marks}{/swallows} } } }",
+    )
+});
+
+/// Label of the nodes, unless told otherwise
+pub static DEFAULT: LazyLock<Template> = LazyLock::new(|| parse_builtin(NORMAL));
+
+/// Every parsed field
+pub static FULL: LazyLock<Template> = LazyLock::new(|| {
+    let rows = NORMAL.strip_suffix('}').unwrap_or(NORMAL);
+    parse_builtin(&format!("{rows}{FULL_ROWS}"))
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Name,
+    /// The focus marker of the theme, on nodes holding the focus
+    Focus,
+    /// Icon of the application, from the application map
+    Glyph,
+    /// How many descendants were hidden
+    Hidden,
+    /// Set when the node has no children, shown or not
+    Leaf,
+    Id,
+    TreeType,
+    Floating,
+    Border,
+    BorderWidth,
+    /// Border width with its unit, when there is a border
+    BorderSize,
+    /// Set when the container has a title bar, and a known border
+    TitleBar,
+    Layout,
+    /// Symbol of the layout
+    LayoutGlyph,
+    /// Layouts of the containers folded into the node
+    Folded,
+    Width,
+    Height,
+    X,
+    Y,
+    Percent,
+    /// Workspace number
+    Num,
+    /// X11 window id
+    Window,
+    /// Size and position on screen, like `800x600+0+20`
+    Rect,
+    /// Set when the window is urgent
+    Urgent,
+    Marks,
+    Swallows,
+    Class,
+    Instance,
+    Title,
+    WindowRole,
+    WindowType,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        Ok(match st {
+            "name" => Self::Name,
+            "focus" => Self::Focus,
+            "glyph" => Self::Glyph,
+            "hidden" => Self::Hidden,
+            "leaf" => Self::Leaf,
+            "id" => Self::Id,
+            "tree_type" => Self::TreeType,
+            "floating" => Self::Floating,
+            "border" => Self::Border,
+            "border_width" => Self::BorderWidth,
+            "border_size" => Self::BorderSize,
+            "title_bar" => Self::TitleBar,
+            "layout" => Self::Layout,
+            "layout_glyph" => Self::LayoutGlyph,
+            "folded" => Self::Folded,
+            "width" => Self::Width,
+            "height" => Self::Height,
+            "x" => Self::X,
+            "y" => Self::Y,
+            "percent" => Self::Percent,
+            "num" => Self::Num,
+            "window" => Self::Window,
+            "rect" => Self::Rect,
+            "urgent" => Self::Urgent,
+            "marks" => Self::Marks,
+            "swallows" => Self::Swallows,
+            "class" => Self::Class,
+            "instance" => Self::Instance,
+            "title" => Self::Title,
+            "window_role" => Self::WindowRole,
+            "window_type" => Self::WindowType,
+            _ => return Err(format!("Unknown template field \"{st}\"")),
+        })
+    }
+}
+
+enum Value {
+    Text(String),
+    Number(f64),
+}
+
+impl Field {
+    fn value(self, node: &Node, settings: &Settings) -> Option<Value> {
+        let text = |text: String| Some(Value::Text(text));
+        // Free text comes from the applications, and may hold anything
+        let clean = |text: &String| Value::Text(text::sanitize(text));
+        let props = node.window_properties.as_ref();
+        let geometry = node.geometry.as_ref();
+        match self {
+            Self::Name => Some(
+                node.name
+                    .as_ref()
+                    .map_or_else(|| Value::Text(settings.lang.tr("no_name").into()), clean),
+            ),
+            Self::Focus => node
+                .has_focus()
+                .then(|| Value::Text(settings.theme.focus_marker.clone())),
+            Self::Glyph => settings
+                .app_map
+                .as_ref()
+                .and_then(|apps| apps.appearance(node)?.glyph.map(str::to_owned))
+                .map(Value::Text),
+            Self::Hidden => text(node.pretty_hidden(settings.lang)),
+            Self::Leaf => node.is_leaf().then(|| Value::Text("leaf".into())),
+            Self::Id => node.id.map(|id| Value::Text(id.to_string())),
+            Self::TreeType => text(node.tree_type.to_string()),
+            Self::Floating => text(settings.lang.tr(node.floating.key()).into()),
+            Self::Border => text(node.border.to_string()),
+            Self::BorderWidth => node
+                .current_border_width
+                .map(|w| Value::Text(w.to_string())),
+            Self::BorderSize => node
+                .current_border_width
+                .filter(|&w| w > 0)
+                .map(|w| Value::Text(format!("{w}{}", node.border.unit()))),
+            Self::TitleBar => (node.border == BorderType::Normal
+                && node.current_border_width.is_some())
+            .then(|| Value::Text(settings.lang.tr("title").into())),
+            Self::Layout => node.layout.as_ref().map(|l| Value::Text(l.to_string())),
+            Self::LayoutGlyph => text(
+                node.layout
+                    .as_ref()
+                    .map_or("•", crate::Layout::glyph)
+                    .into(),
+            ),
+            Self::Folded => node.breadcrumb().map(Value::Text),
+            Self::Width => geometry.map(|g| Value::Text(g.width.to_string())),
+            Self::Height => geometry.map(|g| Value::Text(g.height.to_string())),
+            Self::X => geometry.map(|g| Value::Text(g.x.to_string())),
+            Self::Y => geometry.map(|g| Value::Text(g.y.to_string())),
+            Self::Percent => Some(Value::Number(node.percent * 100_f64)),
+            Self::Num => node.num.map(|num| Value::Text(num.to_string())),
+            Self::Window => node.window.map(|window| Value::Text(window.to_string())),
+            Self::Rect => node
+                .rect
+                .as_ref()
+                .map(|r| Value::Text(format!("{}x{}{:+}{:+}", r.width, r.height, r.x, r.y))),
+            Self::Urgent => node
+                .urgent
+                .then(|| Value::Text(settings.lang.tr("yes").into())),
+            // Lists end up left-aligned, one entry per line
+            Self::Marks => text(node.marks.iter().fold(String::new(), |mut acc, mark| {
+                let _ = writeln!(acc, "- \"{}\"", text::sanitize(mark));
+                acc
+            })),
+            Self::Swallows if settings.no_swallows => None,
+            Self::Swallows => {
+                let mut swallows: Vec<_> = node.swallows.iter().collect();
+                swallows.sort();
+                text(swallows.iter().fold(String::new(), |mut acc, (key, val)| {
+                    let _ = writeln!(acc, "- {key}: \"{}\"", text::sanitize(val));
+                    acc
+                }))
+            }
+            Self::Class => props.and_then(|p| p.class.as_ref()).map(clean),
+            Self::Instance => props.and_then(|p| p.instance.as_ref()).map(clean),
+            Self::Title => props.and_then(|p| p.title.as_ref()).map(clean),
+            Self::WindowRole => props.and_then(|p| p.window_role.as_ref()).map(clean),
+            Self::WindowType => node.window_type.as_ref().map(clean),
+        }
+    }
+
+    /// Whether a section on this field is kept
+    fn is_set(self, node: &Node, settings: &Settings) -> bool {
+        match self.value(node, settings) {
+            Some(Value::Text(text)) => !text.is_empty(),
+            Some(Value::Number(n)) => n != 0_f64,
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    /// Cut after this many columns, or the configured width
+    Trunc(Option<usize>),
+    Upper,
+    Lower,
+    /// Replaces an empty value
+    Default(String),
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        match st.split_once(':') {
+            Some(("trunc", width)) => width
+                .parse()
+                .map(|width| Self::Trunc(Some(width)))
+                .map_err(|e| format!("Invalid truncation width \"{width}\": {e}")),
+            Some(("default", text)) => Ok(Self::Default(text.to_owned())),
+            None if st == "trunc" => Ok(Self::Trunc(None)),
+            None if st == "upper" => Ok(Self::Upper),
+            None if st == "lower" => Ok(Self::Lower),
+            _ => Err(format!("Unknown template filter \"{st}\"")),
+        }
+    }
+}
+
+impl Filter {
+    fn apply(&self, text: String, settings: &Settings) -> String {
+        match self {
+            Self::Trunc(width) => text::truncate(
+                &text,
+                width.unwrap_or(settings.max_width),
+                settings.ellipsis,
+            ),
+            Self::Upper => text.to_uppercase(),
+            Self::Lower => text.to_lowercase(),
+            Self::Default(default) if text.is_empty() => default.clone(),
+            Self::Default(_) => text,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    /// Kept as is, record syntax included
+    Text(String),
+    /// Key of a message, translated when rendering
+    Message(&'static str),
+    Placeholder {
+        field: Field,
+        /// Number of decimals of numbers
+        precision: Option<usize>,
+        filters: Vec<Filter>,
+    },
+    Section {
+        field: Field,
+        /// Kept when the field has no value, rather than when it has one
+        negated: bool,
+        parts: Vec<Self>,
+    },
+}
+
+/// A parsed label template
+#[derive(Clone, Debug)]
+pub struct Template(Vec<Part>);
+
+fn parse_placeholder(inner: &str) -> Result<Part, String> {
+    let mut items = inner.split('|');
+    let head = items.next().unwrap_or_default();
+    let (field, precision) = match head.split_once(':') {
+        Some((field, spec)) => {
+            let precision = spec
+                .strip_prefix("%.")
+                .and_then(|p| p.parse().ok())
+                .ok_or_else(|| format!("Invalid number format \"{spec}\", expected like %.1"))?;
+            (field, Some(precision))
+        }
+        None => (head, None),
+    };
+    Ok(Part::Placeholder {
+        field: field.trim().parse()?,
+        precision,
+        filters: items.map(|f| f.trim().parse()).collect::<Result<_, _>>()?,
+    })
+}
+
+/// Parses parts up to the end of the section on `closing`, if any
+fn parse_parts(
+    chars: &mut Peekable<Chars<'_>>,
+    closing: Option<Field>,
+) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    loop {
+        let Some(c) = chars.next() else {
+            if closing.is_some() {
+                return Err(String::from("Unclosed template section"));
+            }
+            if !text.is_empty() {
+                parts.push(Part::Text(text));
+            }
+            return Ok(parts);
+        };
+        let starts_placeholder = chars
+            .peek()
+            .is_some_and(|&n| n.is_ascii_alphabetic() || matches!(n, '?' | '!' | '/' | '@'));
+        if c != '{' || !starts_placeholder {
+            text.push(c);
+            continue;
+        }
+
+        let mut inner = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(c) => inner.push(c),
+                None => return Err(format!("Unterminated template field \"{{{inner}\"")),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+
+        if let Some(end) = inner.strip_prefix('/') {
+            let field: Field = end.trim().parse()?;
+            if closing != Some(field) {
+                return Err(format!(
+                    "Unexpected end of template section \"{{{inner}}}\""
+                ));
+            }
+            return Ok(parts);
+        }
+        if let Some(key) = inner.strip_prefix('@') {
+            let key = i18n::key(key.trim())
+                .ok_or_else(|| format!("Unknown template message \"{key}\""))?;
+            parts.push(Part::Message(key));
+            continue;
+        }
+        let negated = inner.starts_with('!');
+        if let Some(name) = inner.strip_prefix(['?', '!']) {
+            let field = name.trim().parse()?;
+            parts.push(Part::Section {
+                field,
+                negated,
+                parts: parse_parts(chars, Some(field))?,
+            });
+        } else {
+            parts.push(parse_placeholder(&inner)?);
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        parse_parts(&mut st.chars().peekable(), None).map(Self)
+    }
+}
+
+fn render_parts(parts: &[Part], node: &Node, settings: &Settings, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Message(key) => out.push_str(settings.lang.tr(key)),
+            Part::Placeholder {
+                field,
+                precision,
+                filters,
+            } => {
+                let text = match (field.value(node, settings), precision) {
+                    (Some(Value::Number(n)), Some(precision)) => format!("{n:.precision$}"),
+                    (Some(Value::Number(n)), None) => n.to_string(),
+                    (Some(Value::Text(text)), _) => text,
+                    (None, _) => String::new(),
+                };
+                let text = filters
+                    .iter()
+                    .fold(text, |text, filter| filter.apply(text, settings));
+                out.push_str(&escape_label(&text).replace('\n', "\\l"));
+            }
+            Part::Section {
+                field,
+                negated,
+                parts,
+            } => {
+                if field.is_set(node, settings) != *negated {
+                    render_parts(parts, node, settings, out);
+                }
+            }
+        }
+    }
+}
+
+impl Template {
+    /// Builds the label of a node
+    pub fn render(&self, node: &Node, settings: &Settings) -> String {
+        let mut out = String::new();
+        render_parts(&self.0, node, settings, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{Template, COMPACT, DEFAULT, FULL, MINIMAL};
+    use crate::{parse_trees, Settings};
+
+    const WINDOW: &str = r#"{"type": "con", "name": "Mozilla Firefox", "layout": "splith", "border": "normal", "current_border_width": 2, "floating": "auto_off", "percent": 0.6, "marks": ["web", "main"], "window": 42, "nodes": [], "floating_nodes": [], "window_properties": {"class": "firefox", "instance": "Navigator", "title": "Mozilla Firefox"}}"#;
+
+    /// Renders a template for the first node of a tree
+    fn render(template: &str, tree: &str) -> String {
+        let template: Template = template.parse().expect("the template should parse");
+        let trees = parse_trees(tree).expect("the tree should parse");
+        template.render(&trees[0], &Settings::parse_from(["tuilade"]))
+    }
+
+    fn parse_error(template: &str) -> String {
+        template
+            .parse::<Template>()
+            .expect_err("the template should not parse")
+    }
+
+    /// The built-in templates are parsed on first use, and panic if invalid
+    #[test]
+    fn builtins_parse() {
+        for template in [&MINIMAL, &COMPACT, &DEFAULT, &FULL] {
+            assert!(!template.0.is_empty());
+        }
+    }
+
+    #[test]
+    fn builtins_render() {
+        let trees = parse_trees(WINDOW).expect("the tree should parse");
+        let settings = Settings::parse_from(["tuilade"]);
+        assert_eq!(MINIMAL.render(&trees[0], &settings), "Mozilla Firefox");
+        let full = FULL.render(&trees[0], &settings);
+        assert!(full.starts_with(DEFAULT.render(&trees[0], &settings).trim_end_matches('}')));
+        assert!(full.contains("Navigator"));
+    }
+
+    #[test]
+    fn fields_and_filters() {
+        assert_eq!(render("{name|upper}", WINDOW), "MOZILLA FIREFOX");
+        assert_eq!(render("{name|trunc:10}", WINDOW), "Mozilla Fi...");
+        assert_eq!(render("{percent:%.1}%", WINDOW), "60.0%");
+        assert_eq!(
+            render("{class} ({instance})", WINDOW),
+            "firefox (Navigator)"
+        );
+        assert_eq!(render("{window_role|default:none}", WINDOW), "none");
+    }
+
+    #[test]
+    fn sections_and_messages() {
+        assert_eq!(
+            render("{?marks}{marks}{/marks}{!marks}{@no_marks}{/marks}", WINDOW),
+            r#"- \"web\"\l- \"main\"\l"#
+        );
+        assert_eq!(
+            render(
+                "{?window_role}{window_role}{/window_role}{!window_role}{@no_marks}{/window_role}",
+                WINDOW
+            ),
+            "No marks"
+        );
+        assert_eq!(render("{@width} {name}", WINDOW), "Width: Mozilla Firefox");
+    }
+
+    #[test]
+    fn record_braces_are_kept() {
+        assert_eq!(
+            render("{<NAME>{name}|{ {class} }}", WINDOW),
+            "{<NAME>Mozilla Firefox|{ firefox }}"
+        );
+    }
+
+    #[test]
+    fn field_values_are_escaped() {
+        let tree = WINDOW.replace("Mozilla Firefox", "{a|b} <c>");
+        assert_eq!(
+            render("{<NAME>{name}|{class}}", &tree),
+            r"{<NAME>\{a\|b\} &lt;c&gt;|firefox}"
+        );
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(parse_error("{nmae}"), "Unknown template field \"nmae\"");
+        assert_eq!(
+            parse_error("{?nmae}{/nmae}"),
+            "Unknown template field \"nmae\""
+        );
+    }
+
+    #[test]
+    fn unknown_filter() {
+        assert_eq!(
+            parse_error("{name|reverse}"),
+            "Unknown template filter \"reverse\""
+        );
+    }
+
+    #[test]
+    fn unclosed_section() {
+        assert_eq!(parse_error("{?marks}{marks}"), "Unclosed template section");
+        assert_eq!(
+            parse_error("{name"),
+            "Unterminated template field \"{name\""
+        );
+    }
+
+    #[test]
+    fn mismatched_section_end() {
+        assert_eq!(
+            parse_error("{?marks}{marks}{/name}"),
+            "Unexpected end of template section \"{/name}\""
+        );
+        assert_eq!(
+            parse_error("{name}{/name}"),
+            "Unexpected end of template section \"{/name}\""
+        );
+    }
+}
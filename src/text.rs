@@ -0,0 +1,131 @@
+//! Cleaning and shortening of the text shown in the graph
+//!
+//! Window titles come from the applications, so they may hold anything:
+//! control characters, bidi overrides flipping the rest of the label, wide
+//! characters and emoji made of several code points.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Where long text is cut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Ellipsis {
+    /// Keep the start of the text
+    End,
+    /// Keep both the start and the end of the text
+    Middle,
+}
+
+const ELLIPSIS: &str = "...";
+
+/// Whether a character changes the direction of the text around it
+const fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Removes the control characters and bidi overrides
+pub fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !c.is_control() && !is_bidi_control(c))
+        .collect()
+}
+
+/// Takes graphemes until they fill `width` columns
+fn fill<'a>(graphemes: impl Iterator<Item = &'a str>, width: usize) -> Vec<&'a str> {
+    let mut used = 0;
+    graphemes
+        .take_while(|g| {
+            used += g.width();
+            used <= width
+        })
+        .collect()
+}
+
+/// Shortens text wider than `width` columns, without splitting graphemes
+pub fn truncate(text: &str, width: usize, ellipsis: Ellipsis) -> String {
+    if text.width() <= width {
+        return text.to_owned();
+    }
+    match ellipsis {
+        Ellipsis::End => fill(text.graphemes(true), width).concat() + ELLIPSIS,
+        Ellipsis::Middle => {
+            let tail_width = width / 2;
+            let head = fill(text.graphemes(true), width - tail_width).concat();
+            let mut tail = fill(text.graphemes(true).rev(), tail_width);
+            tail.reverse();
+            head + ELLIPSIS + &tail.concat()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize, truncate, Ellipsis};
+
+    #[test]
+    fn short_text_is_kept() {
+        assert_eq!(truncate("zsh ~", 5, Ellipsis::End), "zsh ~");
+        assert_eq!(truncate("zsh ~", 5, Ellipsis::Middle), "zsh ~");
+    }
+
+    #[test]
+    fn end_ellipsis() {
+        assert_eq!(truncate("Mozilla Firefox", 7, Ellipsis::End), "Mozilla...");
+    }
+
+    #[test]
+    fn middle_ellipsis() {
+        assert_eq!(
+            truncate("/home/user/projects/tuilade", 10, Ellipsis::Middle),
+            "/home...ilade"
+        );
+        assert_eq!(truncate("abcdefghij", 5, Ellipsis::Middle), "abc...ij");
+    }
+
+    #[test]
+    fn wide_characters_count_twice() {
+        assert_eq!(truncate("日本語のタイトル", 6, Ellipsis::End), "日本語...");
+        // A wide character is dropped rather than overflowing the width
+        assert_eq!(truncate("日本語のタイトル", 5, Ellipsis::End), "日本...");
+        assert_eq!(
+            truncate("日本語のタイトル", 8, Ellipsis::Middle),
+            "日本...トル"
+        );
+    }
+
+    #[test]
+    fn emoji_are_not_split() {
+        // Thumbs up with a skin tone modifier, then a family joined by ZWJ
+        let text = "ok 👍🏽 👨‍👩‍👧 done";
+        let cut = truncate(text, 5, Ellipsis::End);
+        assert_eq!(cut, "ok 👍🏽...");
+        let cut = truncate(text, 8, Ellipsis::End);
+        assert_eq!(cut, "ok 👍🏽 👨‍👩‍👧...");
+        assert_eq!(truncate(text, 8, Ellipsis::Middle), "ok ...done");
+        assert_eq!(truncate(text, 9, Ellipsis::Middle), "ok 👍🏽...done");
+    }
+
+    #[test]
+    fn sanitize_removes_controls() {
+        assert_eq!(sanitize("Foo\nexec rm\t-rf\u{7}"), "Fooexec rm-rf");
+    }
+
+    #[test]
+    fn sanitize_removes_bidi_overrides() {
+        assert_eq!(sanitize("invoice\u{202e}fdp.exe"), "invoicefdp.exe");
+        assert_eq!(
+            sanitize("\u{2067}שלום\u{2069} \u{200f}world\u{200e}"),
+            "שלום world"
+        );
+        assert_eq!(sanitize("\u{061c}مرحبا"), "مرحبا");
+    }
+
+    #[test]
+    fn sanitize_keeps_emoji_sequences() {
+        // Zero width joiners and variation selectors are not controls
+        assert_eq!(sanitize("👨‍👩‍👧 ❤️"), "👨‍👩‍👧 ❤️");
+    }
+}
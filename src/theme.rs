@@ -0,0 +1,361 @@
+//! Themes mapping the state of the nodes to DOT styling
+//!
+//! A theme is a TOML file, where every section is a set of DOT attributes.
+//! The sections matching a node are applied in order, later ones taking
+//! precedence: tree type, layout, floating mode, application color, swallows,
+//! marks, urgency, focus, the i3 state of the windows and highlighting. Diffs
+//! add the changes of the nodes on top of these.
+
+use serde_derive::Deserialize;
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{i18n::Lang, i3config::Colors, Node};
+
+/// Built-in themes, by name
+const BUILTIN: &[(&str, &str)] = &[
+    ("light", include_str!("../themes/light.toml")),
+    ("dark", include_str!("../themes/dark.toml")),
+    ("colorblind", include_str!("../themes/colorblind.toml")),
+];
+
+/// DOT attributes of a node, an edge or the graph
+// Reasoning: The fields are named after the DOT attributes they set
+#[allow(clippy::struct_field_names)]
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Style {
+    style: Option<String>,
+    shape: Option<String>,
+    fillcolor: Option<String>,
+    fontcolor: Option<String>,
+    fontname: Option<String>,
+    color: Option<String>,
+    bgcolor: Option<String>,
+    penwidth: Option<f64>,
+}
+
+impl Style {
+    /// Overrides the attributes set in `other`
+    fn merge(&mut self, other: &Self) {
+        let Self {
+            style,
+            shape,
+            fillcolor,
+            fontcolor,
+            fontname,
+            color,
+            bgcolor,
+            penwidth,
+        } = other;
+        if style.is_some() {
+            self.style.clone_from(style);
+        }
+        if shape.is_some() {
+            self.shape.clone_from(shape);
+        }
+        if fillcolor.is_some() {
+            self.fillcolor.clone_from(fillcolor);
+        }
+        if fontcolor.is_some() {
+            self.fontcolor.clone_from(fontcolor);
+        }
+        if fontname.is_some() {
+            self.fontname.clone_from(fontname);
+        }
+        if color.is_some() {
+            self.color.clone_from(color);
+        }
+        if bgcolor.is_some() {
+            self.bgcolor.clone_from(bgcolor);
+        }
+        if penwidth.is_some() {
+            self.penwidth = *penwidth;
+        }
+    }
+
+    /// Whether the style sets no attribute at all
+    fn is_empty(&self) -> bool {
+        self.shape.is_none() && self.attributes().is_empty()
+    }
+
+    /// Shape of the nodes, which should keep fields to be readable
+    pub fn shape(&self) -> &str {
+        self.shape.as_deref().unwrap_or("record")
+    }
+
+    /// The attributes, except for the shape, each preceded by a space
+    pub fn attributes(&self) -> String {
+        let mut attrs = String::new();
+        // A fill color is only painted on filled nodes
+        let style = self
+            .style
+            .as_deref()
+            .or_else(|| self.fillcolor.as_ref().map(|_| "filled"));
+        for (key, value) in [
+            ("style", style),
+            ("fillcolor", self.fillcolor.as_deref()),
+            ("fontcolor", self.fontcolor.as_deref()),
+            ("fontname", self.fontname.as_deref()),
+            ("color", self.color.as_deref()),
+            ("bgcolor", self.bgcolor.as_deref()),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(attrs, " {key}=\"{value}\"");
+            }
+        }
+        if let Some(penwidth) = self.penwidth {
+            let _ = write!(attrs, " penwidth={penwidth}");
+        }
+        attrs
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Shown before the name of the nodes holding the focus
+    pub focus_marker: String,
+    /// Template of the node labels, unless given on the command line
+    pub label_template: Option<String>,
+    graph: Style,
+    node: Style,
+    edge: Style,
+    /// By tree type, like `workspace` or `floating_con`
+    tree_type: HashMap<String, Style>,
+    /// By layout, like `splith` or `tabbed`
+    layout: HashMap<String, Style>,
+    /// By floating mode, like `user_on`
+    floating: HashMap<String, Style>,
+    swallows: Style,
+    marked: Style,
+    urgent: Style,
+    focus_path: Style,
+    focused: Style,
+    focus_edge: Style,
+    /// By i3 window state, like `focused_inactive`
+    client: HashMap<String, Style>,
+    highlight_path: Style,
+    highlight: Style,
+    highlight_edge: Style,
+    /// By change between two trees, like `added` or `resized`
+    changes: HashMap<String, Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let color = |color: &str, penwidth| Style {
+            color: Some(color.to_owned()),
+            penwidth: Some(penwidth),
+            ..Style::default()
+        };
+        Self {
+            focus_marker: String::from("🔴 "),
+            label_template: None,
+            graph: Style::default(),
+            node: Style::default(),
+            edge: Style::default(),
+            tree_type: HashMap::new(),
+            layout: HashMap::new(),
+            floating: HashMap::new(),
+            swallows: Style::default(),
+            marked: Style::default(),
+            urgent: Style::default(),
+            focus_path: Style::default(),
+            focused: Style::default(),
+            focus_edge: Style::default(),
+            client: HashMap::new(),
+            highlight_path: color("#d08700", 2_f64),
+            highlight: Style {
+                fillcolor: Some(String::from("#ffe08a")),
+                ..color("#d08700", 3_f64)
+            },
+            highlight_edge: color("#d08700", 3_f64),
+            changes: [
+                ("added", "#1a7f37", "#dafbe1", None),
+                ("removed", "#cf222e", "#ffebe9", Some("filled,dashed")),
+                ("moved", "#0969da", "#ddf4ff", None),
+                ("resized", "#9a6700", "#fff8c5", None),
+                ("relayouted", "#8250df", "#fbefff", None),
+            ]
+            .into_iter()
+            .map(|(change, border, fill, style)| {
+                let style = Style {
+                    style: style.map(str::to_owned),
+                    fillcolor: Some(fill.to_owned()),
+                    ..color(border, 2_f64)
+                };
+                (change.to_owned(), style)
+            })
+            .collect(),
+        }
+    }
+}
+
+impl Theme {
+    /// Loads a built-in theme by name, or a theme file
+    ///
+    /// The `plain` theme only highlights what was asked for.
+    pub fn load(name: &str) -> Result<Self, String> {
+        if name == "plain" {
+            return Ok(Self::default());
+        }
+        let source = match BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, source)) => (*source).to_owned(),
+            None => std::fs::read_to_string(name)
+                .map_err(|e| format!("Cannot read theme \"{name}\": {e}"))?,
+        };
+        toml::from_str(&source).map_err(|e| format!("Invalid theme \"{name}\": {e}"))
+    }
+
+    /// Takes over the window colors of an i3 configuration
+    pub fn apply_i3_colors(&mut self, colors: &Colors) {
+        for (state, colors) in &colors.0 {
+            self.client.entry(state.clone()).or_default().merge(&Style {
+                fillcolor: Some(colors.background.clone()),
+                fontcolor: Some(colors.text.clone()),
+                color: Some(colors.border.clone()),
+                ..Style::default()
+            });
+        }
+    }
+
+    /// Statements setting the defaults of the graph, its nodes and edges
+    pub fn header(&self) -> String {
+        let mut header = String::new();
+        for (kind, style) in [
+            ("graph", &self.graph),
+            ("node", &self.node),
+            ("edge", &self.edge),
+        ] {
+            let attrs = style.attributes();
+            if !attrs.is_empty() {
+                let _ = writeln!(header, "\t{kind} [{}]", attrs.trim_start());
+            }
+        }
+        header
+    }
+
+    /// Style of a node, on top of the node defaults, filled with the color of
+    /// its application if known
+    pub fn node_style(&self, node: &Node, app_color: Option<&str>) -> Style {
+        let mut style = Style::default();
+        style.shape.clone_from(&self.node.shape);
+        if let Some(s) = self.tree_type.get(&node.tree_type.to_string()) {
+            style.merge(s);
+        }
+        if let Some(s) = node
+            .layout
+            .as_ref()
+            .and_then(|l| self.layout.get(&l.to_string()))
+        {
+            style.merge(s);
+        }
+        if let Some(s) = self.floating.get(node.floating.key()) {
+            style.merge(s);
+        }
+        if let Some(color) = app_color {
+            style.fillcolor = Some(color.to_owned());
+        }
+        let states = [
+            (!node.swallows.is_empty(), &self.swallows),
+            (!node.marks.is_empty(), &self.marked),
+            (node.urgent, &self.urgent),
+            (node.has_focus(), &self.focus_path),
+            (node.focused, &self.focused),
+        ];
+        let highlights = [
+            (node.has_highlight(), &self.highlight_path),
+            (node.highlighted, &self.highlight),
+        ];
+        for (_, s) in states.iter().filter(|(applies, _)| *applies) {
+            style.merge(s);
+        }
+        if let Some(s) = node.client_state().and_then(|state| self.client.get(state)) {
+            style.merge(s);
+        }
+        for (_, s) in highlights.iter().filter(|(applies, _)| *applies) {
+            style.merge(s);
+        }
+        style
+    }
+
+    /// Styles setting nodes apart, with what they mean, in the order they
+    /// apply
+    ///
+    /// The highlight styles are left out unless something is highlighted.
+    pub fn legend_nodes(&self, lang: Lang, highlighting: bool) -> Vec<(String, &Style)> {
+        fn sorted(map: &HashMap<String, Style>) -> Vec<(String, &Style)> {
+            let mut entries: Vec<(String, &Style)> =
+                map.iter().map(|(key, s)| (key.clone(), s)).collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            entries
+        }
+        let mut entries = sorted(&self.tree_type);
+        entries.extend(sorted(&self.layout));
+        entries.extend(sorted(&self.floating).into_iter().map(|(key, s)| {
+            (
+                crate::i18n::key(&key).map_or(key, |k| lang.tr(k).to_owned()),
+                s,
+            )
+        }));
+        entries.extend(
+            [
+                ("swallows", &self.swallows),
+                ("legend_marked", &self.marked),
+                ("legend_urgent", &self.urgent),
+                ("legend_focus_path", &self.focus_path),
+                ("legend_focused", &self.focused),
+            ]
+            .map(|(key, s)| (lang.tr(key).to_owned(), s)),
+        );
+        entries.extend(sorted(&self.client));
+        if highlighting {
+            entries.extend(
+                [
+                    ("legend_highlight_path", &self.highlight_path),
+                    ("legend_highlighted", &self.highlight),
+                ]
+                .map(|(key, s)| (lang.tr(key).to_owned(), s)),
+            );
+        }
+        entries.retain(|(_, s)| !s.is_empty());
+        entries
+    }
+
+    /// Styles setting edges apart, with what they mean
+    pub fn legend_edges(&self, lang: Lang, highlighting: bool) -> Vec<(String, &Style)> {
+        let mut entries = vec![(lang.tr("legend_focus_path"), &self.focus_edge)];
+        if highlighting {
+            entries.push((lang.tr("legend_highlight_path"), &self.highlight_edge));
+        }
+        entries
+            .into_iter()
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(label, s)| (label.to_owned(), s))
+            .collect()
+    }
+
+    /// Style of a node changed between two trees, the later changes taking
+    /// precedence
+    pub fn change_style(&self, node: &Node, changes: &[&str]) -> Style {
+        let mut style = self.node_style(node, None);
+        for change in changes {
+            if let Some(s) = self.changes.get(*change) {
+                style.merge(s);
+            }
+        }
+        style
+    }
+
+    /// Style of the edge leading to a node, on top of the edge defaults
+    pub fn edge_style(&self, child: &Node) -> Style {
+        let mut style = Style::default();
+        if child.has_focus() {
+            style.merge(&self.focus_edge);
+        }
+        if child.has_highlight() {
+            style.merge(&self.highlight_edge);
+        }
+        style
+    }
+}
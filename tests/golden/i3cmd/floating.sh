@@ -0,0 +1,31 @@
+#!/bin/sh
+set -e
+
+windows() {
+    i3-msg -t get_tree | grep -o '"window":[0-9]' | wc -l
+}
+
+launch() {
+    before=$(windows)
+    i3-msg -q "exec --no-startup-id $1"
+    tries=0
+    while [ "$(windows)" -le "$before" ]; do
+        tries=$((tries + 1))
+        if [ "$tries" -gt 100 ]; then
+            echo "No window appeared for $1, going on" >&2
+            break
+        fi
+        sleep 0.1
+    done
+}
+
+i3-msg -q 'workspace "1"'
+i3-msg -q 'layout splith'
+# zsh (class: Alacritty, instance: Alacritty)
+launch alacritty
+# Volume Control (class: Pavucontrol, instance: pavucontrol)
+launch pavucontrol
+i3-msg -q 'floating enable'
+i3-msg -q 'resize set 600 px 400 px'
+i3-msg -q 'move absolute position -20 px 100 px'
+i3-msg -q 'mark --add "mixer"'
@@ -0,0 +1,56 @@
+#!/bin/sh
+set -e
+
+windows() {
+    i3-msg -t get_tree | grep -o '"window":[0-9]' | wc -l
+}
+
+launch() {
+    before=$(windows)
+    i3-msg -q "exec --no-startup-id $1"
+    tries=0
+    while [ "$(windows)" -le "$before" ]; do
+        tries=$((tries + 1))
+        if [ "$tries" -gt 100 ]; then
+            echo "No window appeared for $1, going on" >&2
+            break
+        fi
+        sleep 0.1
+    done
+}
+
+i3-msg -q 'workspace "1"'
+i3-msg -q 'layout splith'
+# Mozilla Firefox — a very long title that is longer than fifty characters for sure (class: firefox, instance: Navigator)
+launch firefox
+i3-msg -q 'mark --add "web"'
+i3-msg -q 'mark --add _tuilade_0'
+# zsh ~ (class: Alacritty, instance: Alacritty)
+launch alacritty
+i3-msg -q 'mark --add _tuilade_1'
+i3-msg -q 'split v'
+# vim 日本語のタイトル (class: Alacritty, instance: Alacritty)
+launch alacritty
+i3-msg -q 'mark --add _tuilade_2'
+i3-msg -q '[con_mark="^_tuilade_1$"] resize set height 50 ppt'
+i3-msg -q 'unmark _tuilade_1'
+i3-msg -q '[con_mark="^_tuilade_2$"] resize set height 50 ppt'
+i3-msg -q 'unmark _tuilade_2'
+i3-msg -q 'focus parent'
+i3-msg -q 'mark --add _tuilade_3'
+i3-msg -q '[con_mark="^_tuilade_0$"] resize set width 60 ppt'
+i3-msg -q 'unmark _tuilade_0'
+i3-msg -q '[con_mark="^_tuilade_3$"] resize set width 40 ppt'
+i3-msg -q 'unmark _tuilade_3'
+i3-msg -q 'workspace "2: chat"'
+i3-msg -q 'layout tabbed'
+# Slack (class: Slack, instance: slack)
+launch slack
+i3-msg -q 'mark --add "chat"'
+i3-msg -q 'split v'
+i3-msg -q 'focus parent'
+i3-msg -q 'split h'
+i3-msg -q 'focus parent'
+# Discord (class: discord, instance: discord)
+launch discord
+i3-msg -q 'workspace "3"'
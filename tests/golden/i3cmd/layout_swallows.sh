@@ -0,0 +1,36 @@
+#!/bin/sh
+set -e
+
+windows() {
+    i3-msg -t get_tree | grep -o '"window":[0-9]' | wc -l
+}
+
+launch() {
+    before=$(windows)
+    i3-msg -q "exec --no-startup-id $1"
+    tries=0
+    while [ "$(windows)" -le "$before" ]; do
+        tries=$((tries + 1))
+        if [ "$tries" -gt 100 ]; then
+            echo "No window appeared for $1, going on" >&2
+            break
+        fi
+        sleep 0.1
+    done
+}
+
+# vim (class: URxvt, instance: vim)
+launch urxvt
+i3-msg -q 'mark --add _tuilade_0'
+i3-msg -q 'split v'
+# shell (class: URxvt, instance: ?)
+launch urxvt
+i3-msg -q 'mark --add _tuilade_1'
+i3-msg -q '[con_mark="^_tuilade_0$"] resize set height 70 ppt'
+i3-msg -q 'unmark _tuilade_0'
+i3-msg -q '[con_mark="^_tuilade_1$"] resize set height 30 ppt'
+i3-msg -q 'unmark _tuilade_1'
+i3-msg -q 'focus parent'
+i3-msg -q 'mark --add "left"'
+# Firefox (class: Firefox, instance: ?)
+launch firefox
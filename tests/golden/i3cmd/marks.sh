@@ -0,0 +1,48 @@
+#!/bin/sh
+set -e
+
+windows() {
+    i3-msg -t get_tree | grep -o '"window":[0-9]' | wc -l
+}
+
+launch() {
+    before=$(windows)
+    i3-msg -q "exec --no-startup-id $1"
+    tries=0
+    while [ "$(windows)" -le "$before" ]; do
+        tries=$((tries + 1))
+        if [ "$tries" -gt 100 ]; then
+            echo "No window appeared for $1, going on" >&2
+            break
+        fi
+        sleep 0.1
+    done
+}
+
+# mail (class: Thunderbird, instance: ?)
+launch thunderbird
+i3-msg -q 'mark --add "mail"'
+i3-msg -q 'mark --add _tuilade_0'
+i3-msg -q 'split v'
+# music (class: Spotify, instance: ?)
+launch spotify
+i3-msg -q 'mark --add "music"'
+i3-msg -q 'mark --add "audio"'
+i3-msg -q 'mark --add _tuilade_1'
+i3-msg -q 'split h'
+# volume (class: Pavucontrol, instance: ?)
+launch pavucontrol
+i3-msg -q 'mark --add _tuilade_2'
+i3-msg -q '[con_mark="^_tuilade_1$"] resize set width 50 ppt'
+i3-msg -q 'unmark _tuilade_1'
+i3-msg -q '[con_mark="^_tuilade_2$"] resize set width 50 ppt'
+i3-msg -q 'unmark _tuilade_2'
+i3-msg -q 'focus parent'
+i3-msg -q 'mark --add "media"'
+i3-msg -q 'mark --add _tuilade_3'
+i3-msg -q '[con_mark="^_tuilade_0$"] resize set height 50 ppt'
+i3-msg -q 'unmark _tuilade_0'
+i3-msg -q '[con_mark="^_tuilade_3$"] resize set height 50 ppt'
+i3-msg -q 'unmark _tuilade_3'
+i3-msg -q 'focus parent'
+i3-msg -q 'mark --add "main"'